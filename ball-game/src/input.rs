@@ -0,0 +1,213 @@
+use bevy::asset::FileAssetIo;
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+/// Where the asset server looks for assets, relative to the crate or executable.
+const ASSET_FOLDER: &str = "assets";
+/// Key and button bindings, relative to `ASSET_FOLDER`.
+const BINDINGS_PATH: &str = "config/bindings.json";
+
+/// Turns keys, buttons and the left stick into [`PlayerAction`]s and [`AnalogMovement`].
+pub struct ActionInputPlugin;
+
+impl Plugin for ActionInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputBindings::load(BINDINGS_PATH))
+            .init_resource::<Input<PlayerAction>>()
+            .init_resource::<AnalogMovement>()
+            .init_resource::<ActiveGamepad>()
+            .add_systems(
+                PreUpdate,
+                (track_active_gamepad, update_action_input)
+                    .chain()
+                    .after(InputSystem),
+            );
+    }
+}
+
+/// Everything the game can be asked to do from an input device. Systems never read keys directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum PlayerAction {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Exit,
+}
+
+/// Which keys and gamepad buttons trigger each action. Any of an action's inputs counts as
+/// pressing it.
+#[derive(Resource, Deserialize, Debug)]
+pub struct InputBindings {
+    pub keys: HashMap<PlayerAction, Vec<KeyCode>>,
+    #[serde(default)]
+    pub buttons: HashMap<PlayerAction, Vec<GamepadButtonType>>,
+    #[serde(default)]
+    pub stick: StickSettings,
+}
+
+/// Left stick tuning. Both values are fractions of full deflection.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct StickSettings {
+    /// Deflection below this is ignored; the rest of the range is rescaled to start from zero.
+    pub deadzone: f32,
+    /// Deflection at or past this runs instead of walking.
+    pub run_threshold: f32,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        StickSettings {
+            deadzone: 0.2,
+            run_threshold: 0.85,
+        }
+    }
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        let keys = [
+            (PlayerAction::MoveLeft, vec![KeyCode::A, KeyCode::Left]),
+            (PlayerAction::MoveRight, vec![KeyCode::D, KeyCode::Right]),
+            (PlayerAction::MoveUp, vec![KeyCode::W, KeyCode::Up]),
+            (PlayerAction::MoveDown, vec![KeyCode::S, KeyCode::Down]),
+            (PlayerAction::Exit, vec![KeyCode::Escape]),
+        ];
+        let buttons = [
+            (PlayerAction::MoveLeft, vec![GamepadButtonType::DPadLeft]),
+            (PlayerAction::MoveRight, vec![GamepadButtonType::DPadRight]),
+            (PlayerAction::MoveUp, vec![GamepadButtonType::DPadUp]),
+            (PlayerAction::MoveDown, vec![GamepadButtonType::DPadDown]),
+            (PlayerAction::Exit, vec![GamepadButtonType::Select]),
+        ];
+
+        InputBindings {
+            keys: keys.into_iter().collect(),
+            buttons: buttons.into_iter().collect(),
+            stick: StickSettings::default(),
+        }
+    }
+}
+
+impl InputBindings {
+    /// Reads bindings from a JSON file under `assets`, keeping the defaults if it is missing or
+    /// invalid. Resolved the same way the asset server does, so it works from any directory.
+    pub fn load(path: &str) -> Self {
+        let path = FileAssetIo::get_base_path().join(ASSET_FOLDER).join(path);
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                serde_json::from_str(&contents).map_err(|error| error.to_string())
+            });
+
+        match parsed {
+            Ok(bindings) => bindings,
+            Err(error) => {
+                warn!(
+                    "Using default key bindings, could not load {}: {}",
+                    path.display(),
+                    error
+                );
+                InputBindings::default()
+            }
+        }
+    }
+}
+
+/// Left stick position after the deadzone. Zero when no gamepad is connected.
+#[derive(Resource, Default)]
+pub struct AnalogMovement {
+    pub direction: Vec2,
+    pub is_running: bool,
+}
+
+/// The gamepad whose buttons and stick are read. Follows connects and disconnects.
+#[derive(Resource, Default)]
+pub struct ActiveGamepad(pub Option<Gamepad>);
+
+/// Picks up the first gamepad to connect, and falls back to any other one when it disconnects.
+pub fn track_active_gamepad(
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+    gamepads: Res<Gamepads>,
+    mut active_gamepad: ResMut<ActiveGamepad>,
+) {
+    for event in connection_events.iter() {
+        match &event.connection {
+            GamepadConnection::Connected(gamepad_info) => {
+                if active_gamepad.0.is_none() {
+                    info!("Using gamepad {}", gamepad_info.name);
+                    active_gamepad.0 = Some(event.gamepad);
+                }
+            }
+            GamepadConnection::Disconnected => {
+                if active_gamepad.0 == Some(event.gamepad) {
+                    active_gamepad.0 = gamepads.iter().find(|gamepad| *gamepad != event.gamepad);
+                }
+            }
+        }
+    }
+}
+
+pub fn update_action_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    active_gamepad: Res<ActiveGamepad>,
+    bindings: Res<InputBindings>,
+    mut action_input: ResMut<Input<PlayerAction>>,
+    mut analog_movement: ResMut<AnalogMovement>,
+) {
+    action_input.clear();
+    for action in [
+        PlayerAction::MoveLeft,
+        PlayerAction::MoveRight,
+        PlayerAction::MoveUp,
+        PlayerAction::MoveDown,
+        PlayerAction::Exit,
+    ] {
+        let key_pressed = bindings
+            .keys
+            .get(&action)
+            .is_some_and(|keys| keyboard_input.any_pressed(keys.iter().copied()));
+        let button_pressed = match (active_gamepad.0, bindings.buttons.get(&action)) {
+            (Some(gamepad), Some(buttons)) => buttons
+                .iter()
+                .any(|button| gamepad_buttons.pressed(GamepadButton::new(gamepad, *button))),
+            _ => false,
+        };
+
+        if key_pressed || button_pressed {
+            action_input.press(action);
+        } else {
+            action_input.release(action);
+        }
+    }
+
+    let stick = active_gamepad.0.map_or(Vec2::ZERO, |gamepad| {
+        let axis = |axis_type| {
+            gamepad_axes
+                .get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.0)
+        };
+        Vec2::new(
+            axis(GamepadAxisType::LeftStickX),
+            axis(GamepadAxisType::LeftStickY),
+        )
+    });
+
+    // Rescale past the deadzone so movement starts from zero at its edge
+    let deadzone = bindings.stick.deadzone;
+    let length = stick.length();
+    let direction = if length <= deadzone {
+        Vec2::ZERO
+    } else {
+        stick / length * ((length - deadzone) / (1.0 - deadzone)).min(1.0)
+    };
+
+    analog_movement.direction = direction;
+    analog_movement.is_running =
+        direction != Vec2::ZERO && direction.length() >= bindings.stick.run_threshold;
+}
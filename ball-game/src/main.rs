@@ -1,17 +1,21 @@
 use bevy::app::AppExit;
 use bevy::prelude::*;
+use bevy::utils::HashMap;
 use bevy::window::PrimaryWindow;
 use rand::prelude::*;
 
-use bevy::audio::prelude::*;
-use bevy::audio::AudioPlugin;
 use bevy::audio::PlaybackMode;
-use bevy::diagnostic::{DiagnosticsPlugin, LogDiagnosticsPlugin};
+use bevy::diagnostic::LogDiagnosticsPlugin;
 
 use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
 use bevy::text::prelude::Text;
 
+mod input;
+
+use crate::input::{ActionInputPlugin, AnalogMovement, PlayerAction};
+
 pub const PLAYER_SPEED: f32 = 500.0;
+pub const PLAYER_RUN_SPEED: f32 = 800.0; // Speed with the stick pushed past the run threshold.
 pub const PLAYER_SIZE: f32 = 64.0; // This is the player sprite size.
 pub const NUMBER_OF_ENEMIES: usize = 4;
 pub const ENEMY_SPEED: f32 = 200.0;
@@ -25,12 +29,13 @@ fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
         .add_plugins(LogDiagnosticsPlugin::default())
-        .add_plugins(FrameTimeDiagnosticsPlugin::default())
+        .add_plugins(FrameTimeDiagnosticsPlugin)
         .init_resource::<Score>()
         .init_resource::<HighScores>()
         .init_resource::<StarSpawnTimer>()
         .init_resource::<EnemySpawnTimer>()
         .add_event::<GameOver>()
+        .add_plugins(ActionInputPlugin)
         .add_systems(Startup, spawn_camera)
         .add_systems(Startup, spawn_player)
         .add_systems(Startup, spawn_enemies)
@@ -40,6 +45,12 @@ fn main() {
         .add_systems(Update, player_movement)
         .add_systems(Update, confine_player_movement)
         .add_systems(Update, enemy_movement)
+        .add_systems(
+            Update,
+            bounce_enemies
+                .after(enemy_movement)
+                .before(handle_enemy_boundary),
+        )
         .add_systems(Update, handle_enemy_boundary)
         .add_systems(Update, enemy_hit_player)
         .add_systems(Update, player_hit_star)
@@ -66,28 +77,16 @@ pub struct Enemy {
 #[derive(Component)]
 pub struct Star {}
 
-#[derive(Resource)]
+#[derive(Resource, Default)]
 pub struct Score {
     pub value: u32,
 }
 
-impl Default for Score {
-    fn default() -> Score {
-        Score { value: 0 }
-    }
-}
-
-#[derive(Resource, Debug)]
+#[derive(Resource, Debug, Default)]
 pub struct HighScores {
     pub scores: Vec<(String, u32)>,
 }
 
-impl Default for HighScores {
-    fn default() -> HighScores {
-        HighScores { scores: Vec::new() }
-    }
-}
-
 #[derive(Resource)]
 pub struct StarSpawnTimer {
     pub timer: Timer,
@@ -249,23 +248,24 @@ pub fn spawn_stars(
 }
 
 pub fn player_movement(
-    keyboard_input: Res<Input<KeyCode>>,
+    action_input: Res<Input<PlayerAction>>,
+    analog_movement: Res<AnalogMovement>,
     mut player_query: Query<&mut Transform, With<Player>>,
     time: Res<Time>,
 ) {
     if let Ok(mut transform) = player_query.get_single_mut() {
         let mut direction = Vec3::ZERO;
 
-        if keyboard_input.pressed(KeyCode::Left) || keyboard_input.pressed(KeyCode::A) {
+        if action_input.pressed(PlayerAction::MoveLeft) {
             direction += Vec3::new(-1.0, 0.0, 0.0);
         }
-        if keyboard_input.pressed(KeyCode::Right) || keyboard_input.pressed(KeyCode::D) {
+        if action_input.pressed(PlayerAction::MoveRight) {
             direction += Vec3::new(1.0, 0.0, 0.0);
         }
-        if keyboard_input.pressed(KeyCode::Up) || keyboard_input.pressed(KeyCode::W) {
+        if action_input.pressed(PlayerAction::MoveUp) {
             direction += Vec3::new(0.0, 1.0, 0.0);
         }
-        if keyboard_input.pressed(KeyCode::Down) || keyboard_input.pressed(KeyCode::S) {
+        if action_input.pressed(PlayerAction::MoveDown) {
             direction += Vec3::new(0.0, -1.0, 0.0);
         }
 
@@ -273,7 +273,17 @@ pub fn player_movement(
             direction = direction.normalize();
         }
 
-        transform.translation += direction * PLAYER_SPEED * time.delta_seconds();
+        // A tilted stick wins over digital input so partial tilts move slower
+        if analog_movement.direction != Vec2::ZERO {
+            direction = analog_movement.direction.extend(0.0);
+        }
+
+        let speed = if analog_movement.is_running {
+            PLAYER_RUN_SPEED
+        } else {
+            PLAYER_SPEED
+        };
+        transform.translation += direction * speed * time.delta_seconds();
     }
 }
 
@@ -316,6 +326,63 @@ pub fn enemy_movement(mut enemy_query: Query<(&mut Transform, &Enemy)>, time: Re
     }
 }
 
+/// Bounces enemies off each other as an elastic collision between equal masses. Enemies are
+/// bucketed into a grid of enemy sized cells first, so each one is only tested against the few
+/// in the cells around it.
+pub fn bounce_enemies(mut enemy_query: Query<(Entity, &mut Transform, &mut Enemy)>) {
+    let cell_of = |position: Vec3| (position.truncate() / ENEMY_SIZE).floor().as_ivec2();
+
+    let mut grid: HashMap<IVec2, Vec<Entity>> = HashMap::default();
+    for (entity, transform, _) in enemy_query.iter() {
+        grid.entry(cell_of(transform.translation))
+            .or_default()
+            .push(entity);
+    }
+
+    let mut pairs = Vec::new();
+    for (entity, transform, _) in enemy_query.iter() {
+        let cell = cell_of(transform.translation);
+        for y in -1..=1 {
+            for x in -1..=1 {
+                let Some(others) = grid.get(&(cell + IVec2::new(x, y))) else {
+                    continue;
+                };
+                // Each pair once, from its lower entity
+                pairs.extend(
+                    others
+                        .iter()
+                        .filter(|&&other| other > entity)
+                        .map(|&other| (entity, other)),
+                );
+            }
+        }
+    }
+
+    for (a, b) in pairs {
+        let Ok([(_, mut a_transform, mut a_enemy), (_, mut b_transform, mut b_enemy)]) =
+            enemy_query.get_many_mut([a, b])
+        else {
+            continue;
+        };
+        let offset = (a_transform.translation - b_transform.translation).truncate();
+        let overlap = ENEMY_SIZE - offset.length();
+        if overlap <= 0.0 {
+            continue;
+        }
+
+        // Push apart so they don't stay stuck together, then swap velocities along the normal
+        let normal = offset.try_normalize().unwrap_or(Vec2::X);
+        a_transform.translation += (normal * overlap / 2.0).extend(0.0);
+        b_transform.translation -= (normal * overlap / 2.0).extend(0.0);
+
+        let closing = (a_enemy.direction - b_enemy.direction).dot(normal);
+        if closing < 0.0 {
+            a_enemy.direction -= normal * closing;
+            b_enemy.direction += normal * closing;
+        }
+    }
+}
+
 pub fn handle_enemy_boundary(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
@@ -532,17 +599,17 @@ pub fn spawn_enemies_over_time(
 }
 
 pub fn exit_game(
-    keyboard_input: Res<Input<KeyCode>>,
+    action_input: Res<Input<PlayerAction>>,
     mut app_exit_event_writer: EventWriter<AppExit>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
+    if action_input.just_pressed(PlayerAction::Exit) {
         app_exit_event_writer.send(AppExit);
     }
 }
 
 pub fn handle_game_over(mut game_over_event_reader: EventReader<GameOver>) {
     for event in game_over_event_reader.iter() {
-        println!("Your final score is: {}", event.score.to_string());
+        println!("Your final score is: {}", event.score);
     }
 }
 
@@ -0,0 +1,374 @@
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+use crate::helpers::aseprite::AsepriteLoader;
+use crate::helpers::json::JsonAssetPlugin;
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(JsonAssetPlugin::<AnimationSet>::new(&["anim.json"]))
+            .add_plugins(JsonAssetPlugin::<AnimationRules>::new(&["rules.json"]))
+            .init_asset_loader::<AsepriteLoader>()
+            .add_event::<AnimationEvent>()
+            .add_systems(Update, animate_sprites.in_set(AnimationSystem));
+    }
+}
+
+/// Runs `animate_sprites`. Systems that pick clips with `Animator::play` should run before it.
+#[derive(Debug, Clone, Copy, SystemSet, PartialEq, Eq, Hash)]
+pub struct AnimationSystem;
+
+/// Named clips for one sprite sheet, plus the rules for moving between them.
+#[derive(Deserialize, TypeUuid, TypePath, Debug)]
+#[uuid = "b004e2f7-25f5-494a-bac8-58d9367dbbee"]
+pub struct AnimationSet {
+    pub clips: HashMap<String, AnimationClip>,
+    #[serde(default)]
+    pub transitions: Vec<AnimationTransition>,
+}
+
+/// Loop modes, frame events and transitions for clips whose frames come from somewhere else, such
+/// as the tags of an Aseprite file. Clips are keyed by the same names as in the `AnimationSet`.
+#[derive(Deserialize, TypeUuid, TypePath, Debug)]
+#[uuid = "6f1d8b3a-c2e4-4a97-8b5d-0e3f7a9c1d24"]
+pub struct AnimationRules {
+    #[serde(default)]
+    pub clips: HashMap<String, ClipRules>,
+    #[serde(default)]
+    pub transitions: Vec<AnimationTransition>,
+}
+
+/// Added to the clip of the same name. Anything set here wins over the clip's own value.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct ClipRules {
+    #[serde(default)]
+    pub mode: Option<LoopMode>,
+    #[serde(default)]
+    pub next: Option<String>,
+    #[serde(default)]
+    pub events: Vec<FrameEvent>,
+}
+
+/// A clip from an `AnimationSet` with the `AnimationRules` for it, if any.
+struct RuledClip<'a> {
+    clip: &'a AnimationClip,
+    rules: Option<&'a ClipRules>,
+}
+
+impl RuledClip<'_> {
+    fn mode(&self) -> LoopMode {
+        self.rules
+            .and_then(|rules| rules.mode)
+            .unwrap_or(self.clip.mode)
+    }
+
+    fn next(&self) -> Option<&String> {
+        self.rules
+            .and_then(|rules| rules.next.as_ref())
+            .or(self.clip.next.as_ref())
+    }
+
+    fn events(&self) -> impl Iterator<Item = &FrameEvent> {
+        let ruled = self.rules.map_or(&[][..], |rules| &rules.events[..]);
+        self.clip.events.iter().chain(ruled)
+    }
+}
+
+fn find_transition<'a>(
+    transitions: &'a [AnimationTransition],
+    from: &str,
+    to: &str,
+) -> Option<&'a AnimationTransition> {
+    transitions
+        .iter()
+        .find(|transition| transition.from == from && transition.to == to)
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AnimationClip {
+    pub frames: Vec<AnimationFrame>,
+    #[serde(default)]
+    pub mode: LoopMode,
+    /// Clip to switch to once a `Once` clip finishes, unless another one was asked for.
+    #[serde(default)]
+    pub next: Option<String>,
+    #[serde(default)]
+    pub events: Vec<FrameEvent>,
+}
+
+/// A named event fired whenever playback reaches `frame`, counted from the start of the clip.
+#[derive(Deserialize, Clone, Debug)]
+pub struct FrameEvent {
+    pub frame: usize,
+    pub name: String,
+}
+
+/// Sent when an animation reaches a frame carrying a `FrameEvent`, so sounds and effects can line
+/// up with the art.
+#[derive(Event, Clone, Debug)]
+pub struct AnimationEvent {
+    pub entity: Entity,
+    pub name: String,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct AnimationFrame {
+    /// Index into the texture atlas.
+    pub index: usize,
+    /// Seconds the frame stays up at normal speed.
+    pub duration: f32,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoopMode {
+    #[default]
+    Loop,
+    /// Plays through once and holds the last frame.
+    Once,
+    /// Plays forwards then backwards, repeating.
+    PingPong,
+}
+
+/// Changes how switching from one clip to another happens. Without a rule the switch is immediate.
+#[derive(Deserialize, Clone, Debug)]
+pub struct AnimationTransition {
+    pub from: String,
+    pub to: String,
+    /// Let `from` play to its end first. Only meaningful for `Once` clips.
+    #[serde(default)]
+    pub wait_for_finish: bool,
+    /// Clip played in between, such as a landing squash between a jump and idle.
+    #[serde(default)]
+    pub via: Option<String>,
+}
+
+/// Plays clips from an `AnimationSet` on the entity's `TextureAtlasSprite`, or its
+/// `UiTextureAtlasImage` in the UI.
+#[derive(Component, Debug)]
+pub struct Animator {
+    pub animations: Handle<AnimationSet>,
+    /// Applied on top of `animations`.
+    pub rules: Option<Handle<AnimationRules>>,
+    /// Playback rate multiplier.
+    pub speed: f32,
+    current: String,
+    /// Clip asked for with `play` that hasn't started yet.
+    queued: Option<String>,
+    /// Whether `current` is the `via` clip of a transition to `queued`.
+    in_transition: bool,
+    frame: usize,
+    elapsed: f32,
+    reversing: bool,
+    finished: bool,
+    /// Set when `current` changed and its first frame still needs to be shown and its events sent.
+    restarted: bool,
+}
+
+impl Animator {
+    pub fn new(animations: Handle<AnimationSet>, clip: &str) -> Self {
+        Animator {
+            animations,
+            rules: None,
+            speed: 1.0,
+            current: clip.to_string(),
+            queued: None,
+            in_transition: false,
+            frame: 0,
+            elapsed: 0.0,
+            reversing: false,
+            finished: false,
+            restarted: true,
+        }
+    }
+
+    pub fn with_rules(mut self, rules: Handle<AnimationRules>) -> Self {
+        self.rules = Some(rules);
+        self
+    }
+
+    /// Asks for `clip` to play. Cheap to call every frame; the clip only restarts when it changes.
+    pub fn play(&mut self, clip: &str) {
+        if self.current == clip && !self.in_transition {
+            self.queued = None;
+        } else if self.queued.as_deref() != Some(clip) {
+            self.queued = Some(clip.to_string());
+        }
+    }
+
+    /// Whether `clip` is playing and has reached the end, which only `Once` clips do.
+    pub fn has_finished(&self, clip: &str) -> bool {
+        self.current == clip && self.finished
+    }
+
+    fn start(&mut self, clip: String) {
+        self.current = clip;
+        self.frame = 0;
+        self.elapsed = 0.0;
+        self.reversing = false;
+        self.finished = false;
+        self.restarted = true;
+    }
+
+    /// Starts the queued clip, or the transition leading to it, unless a rule says to wait.
+    fn apply_queued(&mut self, set: &AnimationSet, rules: Option<&AnimationRules>) {
+        let Some(target) = self.queued.clone() else {
+            return;
+        };
+        if target == self.current {
+            self.queued = None;
+            self.in_transition = false;
+            return;
+        }
+
+        let transition = find_transition(&set.transitions, &self.current, &target).or_else(|| {
+            rules.and_then(|rules| find_transition(&rules.transitions, &self.current, &target))
+        });
+        let must_wait =
+            self.in_transition || transition.is_some_and(|transition| transition.wait_for_finish);
+        if must_wait && !self.finished {
+            return;
+        }
+
+        match transition.and_then(|transition| transition.via.clone()) {
+            Some(via) if !self.in_transition => {
+                self.in_transition = true;
+                self.start(via);
+            }
+            _ => {
+                self.queued = None;
+                self.in_transition = false;
+                self.start(target);
+            }
+        }
+    }
+
+    /// Moves playback forward by `delta_seconds`, pushing every frame it steps onto to `entered`.
+    fn advance(&mut self, clip: &RuledClip, delta_seconds: f32, entered: &mut Vec<usize>) {
+        let frames = &clip.clip.frames;
+        if self.finished || frames.is_empty() {
+            return;
+        }
+
+        // The clip may have been hot reloaded with fewer frames
+        self.frame = self.frame.min(frames.len() - 1);
+        self.elapsed += delta_seconds * self.speed;
+        loop {
+            let duration = frames[self.frame].duration;
+            // A zero duration would never let the loop end
+            if duration <= 0.0 || self.elapsed < duration {
+                break;
+            }
+            self.elapsed -= duration;
+            self.step(frames.len() - 1, clip.mode());
+            if self.finished {
+                break;
+            }
+            entered.push(self.frame);
+        }
+    }
+
+    fn step(&mut self, last: usize, mode: LoopMode) {
+        match mode {
+            LoopMode::Loop => {
+                self.frame = if self.frame >= last {
+                    0
+                } else {
+                    self.frame + 1
+                }
+            }
+            LoopMode::Once => {
+                if self.frame >= last {
+                    self.finished = true;
+                } else {
+                    self.frame += 1;
+                }
+            }
+            LoopMode::PingPong => {
+                if last == 0 {
+                    return;
+                }
+                if (self.reversing && self.frame == 0) || (!self.reversing && self.frame >= last) {
+                    self.reversing = !self.reversing;
+                }
+                if self.reversing {
+                    self.frame -= 1;
+                } else {
+                    self.frame += 1;
+                }
+            }
+        }
+    }
+}
+
+pub fn animate_sprites(
+    time: Res<Time>,
+    animation_sets: Res<Assets<AnimationSet>>,
+    animation_rules: Res<Assets<AnimationRules>>,
+    mut animator_query: Query<(
+        Entity,
+        &mut Animator,
+        Option<&mut TextureAtlasSprite>,
+        Option<&mut UiTextureAtlasImage>,
+    )>,
+    mut animation_events: EventWriter<AnimationEvent>,
+) {
+    let mut entered_frames = Vec::new();
+
+    for (entity, mut animator, sprite, image) in animator_query.iter_mut() {
+        let Some(set) = animation_sets.get(&animator.animations) else {
+            continue;
+        };
+
+        let rules = animator
+            .rules
+            .as_ref()
+            .and_then(|rules| animation_rules.get(rules));
+        animator.apply_queued(set, rules);
+        let Some(clip) = set.clips.get(&animator.current) else {
+            continue;
+        };
+        let clip = RuledClip {
+            clip,
+            rules: rules.and_then(|rules| rules.clips.get(&animator.current)),
+        };
+
+        entered_frames.clear();
+        if animator.restarted {
+            animator.restarted = false;
+            entered_frames.push(animator.frame);
+        }
+        animator.advance(&clip, time.delta_seconds(), &mut entered_frames);
+
+        if let Some(frame) = entered_frames
+            .last()
+            .and_then(|frame| clip.clip.frames.get(*frame))
+        {
+            if let Some(mut sprite) = sprite {
+                sprite.index = frame.index;
+            }
+            if let Some(mut image) = image {
+                image.index = frame.index;
+            }
+        }
+
+        // Every frame crossed this update fires its events, even ones skipped over on a slow frame
+        for frame in entered_frames.iter() {
+            for event in clip.events().filter(|event| event.frame == *frame) {
+                animation_events.send(AnimationEvent {
+                    entity,
+                    name: event.name.clone(),
+                });
+            }
+        }
+
+        if animator.finished && animator.queued.is_none() {
+            if let Some(next) = clip.next() {
+                animator.queued = Some(next.clone());
+            }
+        }
+    }
+}
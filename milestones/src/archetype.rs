@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::HashMap;
+use bevy_asset_loader::prelude::*;
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::behaviour::{BehaviourConfig, BehaviourState, Senses};
+use crate::collider::Collider;
+use crate::collision::{Contacts, KinematicBody};
+use crate::enemy::{Crowding, Enemy};
+use crate::health::Health;
+use crate::navigation::NavAgent;
+use crate::offscreen::DespawnWhenOffscreen;
+use crate::player::Velocity;
+use crate::projectile::{RangedAttack, Weapon};
+use crate::timestep::InterpolatedTransform;
+
+/// Every kind of enemy, keyed by name. Read whenever an enemy spawns, so edits to the file apply
+/// to the next enemy without a restart.
+#[derive(Deserialize, TypeUuid, TypePath, Debug)]
+#[uuid = "5c0e0d1a-8f4b-4d6e-9a57-3b2f1c7e9d40"]
+pub struct EnemyArchetypes {
+    pub archetypes: HashMap<String, EnemyArchetype>,
+}
+
+impl EnemyArchetypes {
+    pub fn get(&self, name: &str) -> Option<&EnemyArchetype> {
+        self.archetypes.get(name)
+    }
+
+    /// Picks an archetype name at random, weighted by `spawn_weight`.
+    pub fn choose(&self, rng: &mut impl Rng) -> Option<&str> {
+        let weighted: Vec<(&String, f32)> = self
+            .archetypes
+            .iter()
+            .map(|(name, archetype)| (name, archetype.spawn_weight))
+            .filter(|(_, weight)| *weight > 0.0)
+            .collect();
+        weighted
+            .choose_weighted(rng, |(_, weight)| *weight)
+            .ok()
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EnemyArchetype {
+    pub sprite: String,
+    /// Width and height of the sprite and diameter of the collider, in pixels.
+    pub size: f32,
+    /// Top speed in pixels per second.
+    pub speed: f32,
+    pub health: u32,
+    pub contact_damage: u32,
+    /// Points for defeating one.
+    pub score: u32,
+    /// Patrol points are offsets from where the enemy spawns.
+    #[serde(default)]
+    pub behaviours: Vec<BehaviourConfig>,
+    #[serde(default)]
+    pub sounds: EnemySounds,
+    /// Walks, jumps and falls along paths over the terrain to wherever its behaviours are heading,
+    /// instead of flying straight there.
+    #[serde(default)]
+    pub navigate: bool,
+    #[serde(default)]
+    pub crowding: Crowding,
+    /// Shoots at the player while they are in sight and range.
+    #[serde(default)]
+    pub ranged: Option<Weapon>,
+    /// How often random spawners pick this archetype relative to the others. Zero keeps it to
+    /// spawners that ask for it by name.
+    #[serde(default = "default_spawn_weight")]
+    pub spawn_weight: f32,
+}
+
+fn default_spawn_weight() -> f32 {
+    1.0
+}
+
+/// Sound file paths, relative to `assets`.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct EnemySounds {
+    /// Played when bouncing off the edge of the window, one picked at random.
+    #[serde(default)]
+    pub bounce: Vec<String>,
+    /// Played when touching the player hurts them.
+    #[serde(default)]
+    pub contact: Option<String>,
+    /// Played when stomped.
+    #[serde(default)]
+    pub defeated: Option<String>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct EnemyAssets {
+    #[asset(path = "config/default.enemies.json")]
+    pub archetypes: Handle<EnemyArchetypes>,
+}
+
+/// Spawns an enemy of `archetype` at `position`, heading in a random direction.
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    archetype: &EnemyArchetype,
+    position: Vec2,
+) -> Entity {
+    let direction = Vec2::from_angle(random::<f32>() * std::f32::consts::TAU);
+    let mut enemy = commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(archetype.size)),
+                ..default()
+            },
+            transform: Transform::from_translation(position.extend(0.0)),
+            texture: asset_server.load(&archetype.sprite),
+            ..default()
+        },
+        Enemy {
+            direction,
+            speed: archetype.speed,
+            contact_damage: archetype.contact_damage,
+            score: archetype.score,
+            sounds: archetype.sounds.clone(),
+        },
+        Collider::Circle {
+            radius: archetype.size / 2.0,
+        },
+        InterpolatedTransform::new(position.extend(0.0)),
+        Health::new(archetype.health, 0, 0.0),
+        archetype.crowding,
+        DespawnWhenOffscreen::default(),
+    ));
+
+    if archetype.navigate {
+        // Walks the level rather than flying through it, so it can follow paths over the ground
+        enemy.insert((
+            NavAgent::default(),
+            KinematicBody::new(Vec2::splat(archetype.size)),
+            Contacts::default(),
+            Velocity { value: Vec3::ZERO },
+        ));
+    }
+    if let Some(weapon) = &archetype.ranged {
+        // Aims with what the enemy senses, even without any behaviours
+        enemy.insert((RangedAttack::new(weapon.clone()), Senses::default()));
+    }
+    if !archetype.behaviours.is_empty() {
+        enemy.insert((Senses::default(), BehaviourState::default()));
+        for behaviour in archetype.behaviours.iter() {
+            behaviour.insert(&mut enemy, position);
+        }
+    }
+    enemy.id()
+}
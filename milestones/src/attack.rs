@@ -0,0 +1,253 @@
+use bevy::prelude::*;
+
+use crate::animation::{AnimationEvent, AnimationSystem, Animator};
+use crate::collider::{Collider, SpatialHash};
+use crate::enemy::Enemy;
+use crate::health::Damage;
+use crate::player::{physics_system, player_movement, PlayerActionState, PlayerInput, PlayerState};
+use crate::prelude::*;
+use crate::timestep::SimulationSet;
+use crate::GameState;
+
+pub struct AttackPlugin;
+
+impl Plugin for AttackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                player_attack
+                    .after(player_movement)
+                    .in_set(SimulationSet::Movement),
+                follow_attack_owner
+                    .after(physics_system)
+                    .in_set(SimulationSet::Physics),
+                attack_hit_enemies.in_set(SimulationSet::Collision),
+                update_attack_hitbox.in_set(SimulationSet::Damage),
+            )
+                .run_if(in_state(GameState::Running)),
+        )
+        .add_systems(
+            Update,
+            time_attack_with_animation
+                .after(AnimationSystem)
+                .run_if(in_state(GameState::Running)),
+        );
+    }
+}
+
+/// Clip played for the kick. Its frame events put the hitbox out and take it back, and the attack
+/// lasts until the clip finishes, so retiming the art retimes the attack. Without frame events the
+/// kick falls back to the `PLAYER_ATTACK_*` timings instead.
+const KICK_CLIP: &str = "kick";
+/// Frame event where the kick starts to connect.
+const HITBOX_OUT_EVENT: &str = "attack_active";
+/// Frame event where the kick stops connecting and recovery begins.
+const HITBOX_IN_EVENT: &str = "attack_recovery";
+
+/// The player's kick: a windup, a short active window with a hitbox out in front, then recovery.
+#[derive(Component)]
+pub struct PlayerAttack {
+    /// Seconds after an attack ends before the next one can start.
+    pub cooldown: f32,
+    in_progress: bool,
+    /// Seconds since the current attack started.
+    elapsed: f32,
+    cooldown_timer: f32,
+    hitbox: Option<Entity>,
+    /// Whether the hitbox should be out, applied on the next fixed step.
+    hitbox_out: bool,
+    /// Set once the kick clip sends a frame event, so the fallback timings stop applying.
+    clip_timed: bool,
+    clip_finished: bool,
+}
+
+impl Default for PlayerAttack {
+    fn default() -> Self {
+        PlayerAttack {
+            cooldown: 0.25,
+            in_progress: false,
+            elapsed: 0.0,
+            cooldown_timer: 0.0,
+            hitbox: None,
+            hitbox_out: false,
+            clip_timed: false,
+            clip_finished: false,
+        }
+    }
+}
+
+/// Damages enemies it overlaps, each at most once per attack.
+#[derive(Component)]
+pub struct AttackHitbox {
+    pub owner: Entity,
+    pub damage: u32,
+    pub knockback: f32,
+    already_hit: Vec<Entity>,
+}
+
+/// Where the hitbox sits relative to its owner, flipped with the way the owner faces.
+fn hitbox_translation(owner: &Transform) -> Vec3 {
+    let facing = owner.scale.x.signum();
+    owner.translation + Vec3::new(facing * PLAYER_ATTACK_REACH, 0.0, 0.0)
+}
+
+pub fn player_attack(
+    mut input: ResMut<PlayerInput>,
+    mut player_query: Query<(&mut PlayerAttack, &mut PlayerState)>,
+    fixed_time: Res<FixedTime>,
+) {
+    let delta_seconds = fixed_time.period.as_secs_f32();
+
+    for (mut attack, mut player_state) in player_query.iter_mut() {
+        attack.cooldown_timer = (attack.cooldown_timer - delta_seconds).max(0.0);
+
+        if std::mem::take(&mut input.attack_pressed)
+            && !attack.in_progress
+            && attack.cooldown_timer == 0.0
+        {
+            attack.in_progress = true;
+            attack.elapsed = 0.0;
+            attack.hitbox_out = false;
+            attack.clip_timed = false;
+            attack.clip_finished = false;
+        }
+
+        if attack.in_progress {
+            // Held over jumping and walking so the kick animation plays out
+            player_state.action_state = PlayerActionState::Attacking;
+        }
+    }
+}
+
+/// Follows the kick's frame events and notes when the clip is done. The hitbox itself is put out
+/// and taken back in `update_attack_hitbox`, on the fixed step.
+pub fn time_attack_with_animation(
+    mut animation_events: EventReader<AnimationEvent>,
+    mut attacker_query: Query<(&Animator, &mut PlayerAttack)>,
+) {
+    for event in animation_events.iter() {
+        let Ok((_, mut attack)) = attacker_query.get_mut(event.entity) else {
+            continue;
+        };
+        if !attack.in_progress {
+            continue;
+        }
+
+        match event.name.as_str() {
+            HITBOX_OUT_EVENT => attack.hitbox_out = true,
+            HITBOX_IN_EVENT => attack.hitbox_out = false,
+            _ => continue,
+        }
+        attack.clip_timed = true;
+    }
+
+    for (animator, mut attack) in attacker_query.iter_mut() {
+        if attack.in_progress && animator.has_finished(KICK_CLIP) {
+            attack.clip_finished = true;
+        }
+    }
+}
+
+/// Puts the hitbox out or takes it back in, and ends the attack once the kick has played. Falls
+/// back to fixed timings when the kick clip is missing or never sends its events, and gives up
+/// after `PLAYER_ATTACK_TIMEOUT` if the clip never finishes.
+pub fn update_attack_hitbox(
+    mut commands: Commands,
+    mut attacker_query: Query<(Entity, &Transform, &mut PlayerAttack, &mut PlayerState)>,
+    fixed_time: Res<FixedTime>,
+) {
+    let delta_seconds = fixed_time.period.as_secs_f32();
+
+    for (entity, transform, mut attack, mut player_state) in attacker_query.iter_mut() {
+        if !attack.in_progress {
+            continue;
+        }
+        attack.elapsed += delta_seconds;
+
+        if !attack.clip_timed {
+            attack.hitbox_out =
+                (PLAYER_ATTACK_ACTIVE_START..PLAYER_ATTACK_ACTIVE_END).contains(&attack.elapsed);
+        }
+        let time_limit = if attack.clip_timed {
+            PLAYER_ATTACK_TIMEOUT
+        } else {
+            PLAYER_ATTACK_DURATION
+        };
+        let finished = attack.clip_finished || attack.elapsed >= time_limit;
+
+        match attack.hitbox {
+            None if attack.hitbox_out && !finished => {
+                let hitbox = commands
+                    .spawn((
+                        Transform::from_translation(hitbox_translation(transform)),
+                        Collider::Aabb {
+                            half_extents: Vec2::new(PLAYER_ATTACK_WIDTH, PLAYER_ATTACK_HEIGHT)
+                                / 2.0,
+                        },
+                        AttackHitbox {
+                            owner: entity,
+                            damage: PLAYER_ATTACK_DAMAGE,
+                            knockback: PLAYER_ATTACK_KNOCKBACK,
+                            already_hit: Vec::new(),
+                        },
+                    ))
+                    .id();
+                attack.hitbox = Some(hitbox);
+            }
+            Some(hitbox) if !attack.hitbox_out || finished => {
+                commands.entity(hitbox).despawn();
+                attack.hitbox = None;
+            }
+            _ => {}
+        }
+
+        if finished {
+            attack.in_progress = false;
+            attack.cooldown_timer = attack.cooldown;
+            player_state.action_state = PlayerActionState::Idle;
+        }
+    }
+}
+
+/// Keeps hitboxes in front of whoever is attacking once they have moved this tick.
+pub fn follow_attack_owner(
+    mut hitbox_query: Query<(&mut Transform, &AttackHitbox)>,
+    owner_query: Query<&Transform, Without<AttackHitbox>>,
+) {
+    for (mut transform, hitbox) in hitbox_query.iter_mut() {
+        if let Ok(owner_transform) = owner_query.get(hitbox.owner) {
+            transform.translation = hitbox_translation(owner_transform);
+        }
+    }
+}
+
+pub fn attack_hit_enemies(
+    mut commands: Commands,
+    mut damage_events: EventWriter<Damage>,
+    spatial_hash: Res<SpatialHash>,
+    mut hitbox_query: Query<(Entity, &mut AttackHitbox)>,
+    enemy_query: Query<(), With<Enemy>>,
+    owner_query: Query<&Transform>,
+) {
+    for (hitbox_entity, mut hitbox) in hitbox_query.iter_mut() {
+        let Ok(owner_transform) = owner_query.get(hitbox.owner) else {
+            // The attacker died mid-swing
+            commands.entity(hitbox_entity).despawn();
+            continue;
+        };
+
+        for other in spatial_hash.overlapping(hitbox_entity) {
+            if !enemy_query.contains(other) || hitbox.already_hit.contains(&other) {
+                continue;
+            }
+            hitbox.already_hit.push(other);
+            damage_events.send(Damage {
+                target: other,
+                amount: hitbox.damage,
+                source: owner_transform.translation.truncate(),
+                knockback: hitbox.knockback,
+            });
+        }
+    }
+}
@@ -0,0 +1,454 @@
+use std::any::TypeId;
+
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::collision::{KinematicBody, TerrainColliders, TILE_SIZE};
+use crate::enemy::{enemy_movement, Enemy};
+use crate::navigation::{feet, NavAgent};
+use crate::prelude::*;
+use crate::timestep::SimulationSet;
+use crate::GameState;
+use crate::Player;
+
+/// How close a patrolling or chasing enemy has to get to a point to count as there.
+const ARRIVE_DISTANCE: f32 = 8.0;
+
+/// Drives enemies with a small state machine per enemy. Each behaviour is a component; every tick
+/// the behaviours an enemy has bid for control, the highest bid wins and steers the enemy.
+///
+/// A new behaviour is a component implementing [`Behaviour`], registered with
+/// [`AddBehaviour::add_behaviour`]. Enemies pick it up by having the component inserted.
+pub struct EnemyBehaviourPlugin;
+
+impl Plugin for EnemyBehaviourPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(
+            FixedUpdate,
+            (
+                BehaviourSet::Sense.run_if(in_state(GameState::Running)),
+                BehaviourSet::Bid.run_if(in_state(GameState::Running)),
+                BehaviourSet::Select.run_if(in_state(GameState::Running)),
+                BehaviourSet::Steer.run_if(in_state(GameState::Running)),
+            )
+                .chain()
+                .before(enemy_movement)
+                .in_set(SimulationSet::Movement),
+        )
+        .add_systems(FixedUpdate, sense_player.in_set(BehaviourSet::Sense))
+        .add_systems(FixedUpdate, select_behaviour.in_set(BehaviourSet::Select))
+        .add_behaviour::<Patrol>()
+        .add_behaviour::<Chase>()
+        .add_behaviour::<Flee>()
+        .add_behaviour::<Wander>();
+    }
+}
+
+/// The steps enemies think in each tick, before `enemy_movement` moves them.
+#[derive(Debug, Clone, Copy, SystemSet, PartialEq, Eq, Hash)]
+pub enum BehaviourSet {
+    /// Updates what each enemy knows about the player.
+    Sense,
+    /// Every behaviour says how much it wants control.
+    Bid,
+    /// The highest bid takes over.
+    Select,
+    /// The behaviour in control sets the enemy's direction.
+    Steer,
+}
+
+/// A way for an enemy to act, competing with its other behaviours for control.
+pub trait Behaviour: Component {
+    /// Shown in the log when an enemy switches behaviour.
+    const NAME: &'static str;
+
+    /// How much this behaviour wants control given what the enemy knows, or `None` if it doesn't
+    /// apply. `active` is whether it is in control right now, so a behaviour can hold on longer
+    /// than it took to start.
+    fn bid(&self, senses: &Senses, active: bool) -> Option<f32>;
+
+    /// Direction to move this tick, as a fraction of the enemy's speed. Only called while in
+    /// control. `heading` is the direction the enemy is moving in now.
+    fn steer(&mut self, position: Vec2, heading: Vec2, senses: &Senses, delta_seconds: f32)
+        -> Vec2;
+
+    /// Where the behaviour is trying to get to, if anywhere. Enemies with a [`NavAgent`] follow a
+    /// path there around the terrain instead of the direction from [`Behaviour::steer`].
+    fn destination(&self) -> Option<Vec2> {
+        None
+    }
+}
+
+pub trait AddBehaviour {
+    fn add_behaviour<B: Behaviour>(&mut self) -> &mut Self;
+}
+
+impl AddBehaviour for App {
+    fn add_behaviour<B: Behaviour>(&mut self) -> &mut Self {
+        self.add_systems(
+            FixedUpdate,
+            (
+                bid_for_control::<B>.in_set(BehaviourSet::Bid),
+                steer_enemy::<B>.in_set(BehaviourSet::Steer),
+            ),
+        )
+    }
+}
+
+/// A behaviour as written in an enemy archetype file, e.g. `{ "Chase": { "sight_radius": 200 } }`.
+/// Fields left out keep their defaults.
+#[derive(Deserialize, Clone, Debug)]
+pub enum BehaviourConfig {
+    Patrol(Patrol),
+    Chase(Chase),
+    Flee(Flee),
+    Wander(Wander),
+}
+
+impl BehaviourConfig {
+    /// Adds the behaviour to `enemy`, taking patrol points as offsets from `home`.
+    pub fn insert(&self, enemy: &mut EntityCommands, home: Vec2) {
+        match self.clone() {
+            BehaviourConfig::Patrol(mut patrol) => {
+                for point in patrol.points.iter_mut() {
+                    *point += home;
+                }
+                enemy.insert(patrol)
+            }
+            BehaviourConfig::Chase(chase) => enemy.insert(chase),
+            BehaviourConfig::Flee(flee) => enemy.insert(flee),
+            BehaviourConfig::Wander(wander) => enemy.insert(wander),
+        };
+    }
+}
+
+/// What an enemy knows about the player this tick.
+#[derive(Component, Default, Debug)]
+pub struct Senses {
+    /// Offset from the enemy to the player, if there is a player.
+    pub to_player: Option<Vec2>,
+    /// Whether no solid terrain lies between the enemy and the player.
+    pub player_visible: bool,
+}
+
+impl Senses {
+    pub fn player_distance(&self) -> Option<f32> {
+        self.to_player.map(|offset| offset.length())
+    }
+}
+
+/// Which behaviour is steering an enemy. Enemies with this but no behaviour that applies stand
+/// still.
+#[derive(Component, Default, Debug)]
+pub struct BehaviourState {
+    active: Option<(TypeId, &'static str)>,
+    /// Highest bid so far this tick.
+    best: Option<(TypeId, &'static str, f32)>,
+}
+
+impl BehaviourState {
+    fn is_active<B: Behaviour>(&self) -> bool {
+        self.active.map(|(id, _)| id) == Some(TypeId::of::<B>())
+    }
+}
+
+/// Walks between `points` in order, looping back to the first.
+#[derive(Component, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Patrol {
+    pub points: Vec<Vec2>,
+    pub speed: f32,
+    pub priority: f32,
+    #[serde(skip)]
+    next: usize,
+}
+
+impl Default for Patrol {
+    fn default() -> Self {
+        Patrol {
+            points: Vec::new(),
+            speed: 0.5,
+            priority: 1.0,
+            next: 0,
+        }
+    }
+}
+
+impl Patrol {
+    pub fn new(points: Vec<Vec2>) -> Self {
+        Patrol {
+            points,
+            ..Default::default()
+        }
+    }
+}
+
+impl Behaviour for Patrol {
+    const NAME: &'static str = "patrol";
+
+    fn bid(&self, _senses: &Senses, _active: bool) -> Option<f32> {
+        (!self.points.is_empty()).then_some(self.priority)
+    }
+
+    fn steer(
+        &mut self,
+        position: Vec2,
+        _heading: Vec2,
+        _senses: &Senses,
+        _delta_seconds: f32,
+    ) -> Vec2 {
+        self.next %= self.points.len();
+        if position.distance(self.points[self.next]) < ARRIVE_DISTANCE {
+            self.next = (self.next + 1) % self.points.len();
+        }
+        (self.points[self.next] - position).normalize_or_zero() * self.speed
+    }
+
+    fn destination(&self) -> Option<Vec2> {
+        self.points.get(self.next).copied()
+    }
+}
+
+/// Runs at the player once they are in sight and close, and keeps after them until they get
+/// `lose_radius` away. If the player ducks out of sight it heads to where they were last seen.
+#[derive(Component, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Chase {
+    pub sight_radius: f32,
+    pub lose_radius: f32,
+    pub speed: f32,
+    pub priority: f32,
+    #[serde(skip)]
+    last_seen: Option<Vec2>,
+}
+
+impl Default for Chase {
+    fn default() -> Self {
+        Chase {
+            sight_radius: ENEMY_SIGHT_RADIUS,
+            lose_radius: ENEMY_LOSE_SIGHT_RADIUS,
+            speed: 1.0,
+            priority: 2.0,
+            last_seen: None,
+        }
+    }
+}
+
+impl Behaviour for Chase {
+    const NAME: &'static str = "chase";
+
+    fn bid(&self, senses: &Senses, active: bool) -> Option<f32> {
+        let distance = senses.player_distance()?;
+        let radius = if active {
+            self.lose_radius
+        } else {
+            self.sight_radius
+        };
+        let in_sight = senses.player_visible && distance < radius;
+        let searching = active && self.last_seen.is_some();
+        (in_sight || searching).then_some(self.priority)
+    }
+
+    fn steer(
+        &mut self,
+        position: Vec2,
+        _heading: Vec2,
+        senses: &Senses,
+        _delta_seconds: f32,
+    ) -> Vec2 {
+        match senses.to_player {
+            Some(to_player) if senses.player_visible && to_player.length() < self.lose_radius => {
+                self.last_seen = Some(position + to_player);
+            }
+            _ => {}
+        }
+        let Some(target) = self.last_seen else {
+            return Vec2::ZERO;
+        };
+        if position.distance(target) < ARRIVE_DISTANCE {
+            // Reached the last known position without spotting them again, so give up
+            self.last_seen = None;
+        }
+        (target - position).normalize_or_zero() * self.speed
+    }
+
+    fn destination(&self) -> Option<Vec2> {
+        self.last_seen
+    }
+}
+
+/// Runs directly away from the player while they are within `radius`, seen or not.
+#[derive(Component, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Flee {
+    pub radius: f32,
+    pub speed: f32,
+    pub priority: f32,
+}
+
+impl Default for Flee {
+    fn default() -> Self {
+        Flee {
+            radius: ENEMY_FLEE_RADIUS,
+            speed: 1.0,
+            priority: 3.0,
+        }
+    }
+}
+
+impl Behaviour for Flee {
+    const NAME: &'static str = "flee";
+
+    fn bid(&self, senses: &Senses, _active: bool) -> Option<f32> {
+        let distance = senses.player_distance()?;
+        (distance < self.radius).then_some(self.priority)
+    }
+
+    fn steer(
+        &mut self,
+        _position: Vec2,
+        heading: Vec2,
+        senses: &Senses,
+        _delta_seconds: f32,
+    ) -> Vec2 {
+        match senses.to_player {
+            Some(to_player) if to_player != Vec2::ZERO => -to_player.normalize() * self.speed,
+            _ => heading,
+        }
+    }
+}
+
+/// Drifts about, turning a little at random each tick. Bounces off the window edges with
+/// `handle_enemy_boundary`.
+#[derive(Component, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Wander {
+    /// Most the heading can turn, in radians per second.
+    pub turn_rate: f32,
+    pub speed: f32,
+    pub priority: f32,
+}
+
+impl Default for Wander {
+    fn default() -> Self {
+        Wander {
+            turn_rate: 3.0,
+            speed: 0.6,
+            priority: 0.5,
+        }
+    }
+}
+
+impl Behaviour for Wander {
+    const NAME: &'static str = "wander";
+
+    fn bid(&self, _senses: &Senses, _active: bool) -> Option<f32> {
+        Some(self.priority)
+    }
+
+    fn steer(
+        &mut self,
+        _position: Vec2,
+        heading: Vec2,
+        _senses: &Senses,
+        delta_seconds: f32,
+    ) -> Vec2 {
+        let mut rng = thread_rng();
+        let heading = if heading == Vec2::ZERO {
+            Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU))
+        } else {
+            heading.normalize()
+        };
+        let turn = rng.gen_range(-1.0..=1.0) * self.turn_rate * delta_seconds;
+        Vec2::from_angle(turn).rotate(heading) * self.speed
+    }
+}
+
+pub fn sense_player(
+    terrain: Res<TerrainColliders>,
+    player_query: Query<&Transform, With<Player>>,
+    mut enemy_query: Query<(&Transform, &mut Senses), With<Enemy>>,
+) {
+    let player_position = player_query
+        .get_single()
+        .ok()
+        .map(|transform| transform.translation.truncate());
+
+    for (transform, mut senses) in enemy_query.iter_mut() {
+        let position = transform.translation.truncate();
+        senses.to_player = player_position.map(|player| player - position);
+        senses.player_visible =
+            player_position.is_some_and(|player| terrain.line_of_sight(position, player));
+    }
+}
+
+fn bid_for_control<B: Behaviour>(mut query: Query<(&B, &Senses, &mut BehaviourState)>) {
+    for (behaviour, senses, mut state) in query.iter_mut() {
+        let Some(bid) = behaviour.bid(senses, state.is_active::<B>()) else {
+            continue;
+        };
+        if state.best.is_none_or(|(_, _, best)| bid > best) {
+            state.best = Some((TypeId::of::<B>(), B::NAME, bid));
+        }
+    }
+}
+
+pub fn select_behaviour(mut query: Query<(Entity, &mut BehaviourState, &mut Enemy)>) {
+    for (entity, mut state, mut enemy) in query.iter_mut() {
+        let winner = state.best.take().map(|(id, name, _)| (id, name));
+        if winner != state.active {
+            debug!(
+                "{:?} switched from {} to {}",
+                entity,
+                state.active.map_or("nothing", |(_, name)| name),
+                winner.map_or("nothing", |(_, name)| name),
+            );
+            state.active = winner;
+        }
+        if winner.is_none() {
+            enemy.direction = Vec2::ZERO;
+        }
+    }
+}
+
+fn steer_enemy<B: Behaviour>(
+    fixed_time: Res<FixedTime>,
+    mut query: Query<(
+        &mut B,
+        &BehaviourState,
+        &Senses,
+        &Transform,
+        &mut Enemy,
+        Option<(&mut NavAgent, &KinematicBody)>,
+    )>,
+) {
+    let delta_seconds = fixed_time.period.as_secs_f32();
+
+    for (mut behaviour, state, senses, transform, mut enemy, navigation) in query.iter_mut() {
+        if !state.is_active::<B>() {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let mut direction = behaviour.steer(position, enemy.direction, senses, delta_seconds);
+
+        if let Some((mut agent, body)) = navigation {
+            match behaviour.destination() {
+                Some(destination) => {
+                    agent.seek(destination);
+                    let footing = feet(position, body);
+                    // Straight there until the first path comes back, or if there is none
+                    if let Some(waypoint) = agent.waypoint(footing) {
+                        // Only which way to walk; jumps and gravity take care of the height.
+                        // Slows down right under or over a waypoint instead of jittering past it
+                        let across = ((waypoint.x - footing.x) / TILE_SIZE).clamp(-1.0, 1.0);
+                        direction = Vec2::new(across, 0.0) * direction.length();
+                    }
+                }
+                None => agent.stop(),
+            }
+        }
+        enemy.direction = direction;
+    }
+}
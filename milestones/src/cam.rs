@@ -1,8 +1,11 @@
+use crate::collision::Aabb;
 use crate::player::Player;
+use crate::timestep::interpolate_transforms;
 use crate::GameState;
 use bevy::app::App;
 use bevy::input::mouse::MouseWheel;
 use bevy::prelude::*;
+use bevy::transform::TransformSystem;
 use bevy::window::PrimaryWindow;
 
 //use crate::prelude::*;
@@ -12,8 +15,11 @@ impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, spawn_camera)
             .add_systems(
-                Update,
-                camera_follow_system.run_if(in_state(GameState::Running)),
+                PostUpdate,
+                camera_follow_system
+                    .after(interpolate_transforms)
+                    .before(TransformSystem::TransformPropagate)
+                    .run_if(in_state(GameState::Running)),
             )
             .add_systems(
                 Update,
@@ -68,3 +74,13 @@ pub fn camera_zoom_system(
         transform.scale *= zoom;
     }
 }
+
+/// The part of the world a camera shows, including its zoom.
+pub fn visible_area(transform: &Transform, projection: &OrthographicProjection) -> Aabb {
+    let scale = transform.scale.truncate();
+    let center = transform.translation.truncate();
+    Aabb {
+        min: center + projection.area.min * scale,
+        max: center + projection.area.max * scale,
+    }
+}
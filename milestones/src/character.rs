@@ -0,0 +1,236 @@
+use bevy::prelude::*;
+
+use crate::animation::Animator;
+use crate::input::PlayerAction;
+use crate::GameState;
+use crate::PlayerAnimation;
+
+const CARD_COLOR: Color = Color::rgb(0.1, 0.2, 0.3);
+const SELECTED_CARD_COLOR: Color = Color::rgb(0.25, 0.4, 0.55);
+
+pub struct CharacterSelectPlugin;
+
+impl Plugin for CharacterSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectedCharacter>()
+            .add_systems(OnEnter(GameState::CharacterSelect), spawn_character_select)
+            .add_systems(
+                Update,
+                (choose_character, highlight_selected_character)
+                    .chain()
+                    .run_if(in_state(GameState::CharacterSelect)),
+            )
+            .add_systems(OnExit(GameState::CharacterSelect), despawn_character_select);
+    }
+}
+
+/// The four DinoSprites sheets the player can pick from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Character {
+    #[default]
+    Doux,
+    Mort,
+    Tard,
+    Vita,
+}
+
+impl Character {
+    /// Left to right, the order they are shown in.
+    pub const ALL: [Character; 4] = [
+        Character::Doux,
+        Character::Mort,
+        Character::Tard,
+        Character::Vita,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Character::Doux => "Doux",
+            Character::Mort => "Mort",
+            Character::Tard => "Tard",
+            Character::Vita => "Vita",
+        }
+    }
+
+    /// Movement tuning file giving this dino its own speeds and jump.
+    pub fn tuning_path(self) -> &'static str {
+        match self {
+            Character::Doux => "config/characters/doux.movement.json",
+            Character::Mort => "config/characters/mort.movement.json",
+            Character::Tard => "config/characters/tard.movement.json",
+            Character::Vita => "config/characters/vita.movement.json",
+        }
+    }
+}
+
+/// The dino picked on the select screen, read by `spawn_player`.
+#[derive(Resource, Default, Debug)]
+pub struct SelectedCharacter(pub Character);
+
+#[derive(Component)]
+struct CharacterSelectScreen;
+
+/// One dino's card on the select screen.
+#[derive(Component)]
+struct CharacterCard(Character);
+
+fn spawn_character_select(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    animations: Res<PlayerAnimation>,
+) {
+    let font: Handle<Font> = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(32.0),
+                    ..Default::default()
+                },
+                background_color: Color::rgb(0.05, 0.05, 0.1).into(),
+                ..Default::default()
+            },
+            CharacterSelectScreen,
+            Name::new("Character Select"),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Choose your dino",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                },
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(24.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for character in Character::ALL {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        width: Val::Px(160.0),
+                                        height: Val::Px(200.0),
+                                        flex_direction: FlexDirection::Column,
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        row_gap: Val::Px(12.0),
+                                        ..Default::default()
+                                    },
+                                    background_color: BackgroundColor(CARD_COLOR),
+                                    ..Default::default()
+                                },
+                                CharacterCard(character),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    AtlasImageBundle {
+                                        style: Style {
+                                            width: Val::Px(96.0),
+                                            height: Val::Px(96.0),
+                                            ..Default::default()
+                                        },
+                                        texture_atlas: animations.sheet(character),
+                                        texture_atlas_image: UiTextureAtlasImage {
+                                            index: 0,
+                                            ..Default::default()
+                                        },
+                                        ..Default::default()
+                                    },
+                                    Animator::new(animations.animations.clone(), "idle"),
+                                ));
+                                parent.spawn(TextBundle::from_section(
+                                    character.name(),
+                                    TextStyle {
+                                        font: font.clone(),
+                                        font_size: 24.0,
+                                        color: Color::WHITE,
+                                    },
+                                ));
+                            });
+                    }
+                });
+
+            parent.spawn(TextBundle::from_section(
+                "Left / Right to choose, Enter to play",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 20.0,
+                    color: Color::GRAY,
+                },
+            ));
+        });
+}
+
+/// Browses the cards with the movement actions and starts the game on confirm or click.
+fn choose_character(
+    action_input: Res<Input<PlayerAction>>,
+    card_query: Query<(&CharacterCard, &Interaction), Changed<Interaction>>,
+    mut selected: ResMut<SelectedCharacter>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let count = Character::ALL.len();
+    let index = Character::ALL
+        .iter()
+        .position(|character| *character == selected.0)
+        .unwrap_or(0);
+
+    if action_input.just_pressed(PlayerAction::MoveRight) {
+        selected.0 = Character::ALL[(index + 1) % count];
+    }
+    if action_input.just_pressed(PlayerAction::MoveLeft) {
+        selected.0 = Character::ALL[(index + count - 1) % count];
+    }
+
+    for (card, interaction) in card_query.iter() {
+        match *interaction {
+            Interaction::Pressed => {
+                selected.0 = card.0;
+                next_state.set(GameState::Running);
+            }
+            Interaction::Hovered => selected.0 = card.0,
+            Interaction::None => {}
+        }
+    }
+
+    if action_input.just_pressed(PlayerAction::MenuConfirm) {
+        next_state.set(GameState::Running);
+    }
+}
+
+fn highlight_selected_character(
+    selected: Res<SelectedCharacter>,
+    mut card_query: Query<(&CharacterCard, &mut BackgroundColor)>,
+) {
+    for (card, mut background_color) in card_query.iter_mut() {
+        *background_color = if card.0 == selected.0 {
+            BackgroundColor(SELECTED_CARD_COLOR)
+        } else {
+            BackgroundColor(CARD_COLOR)
+        };
+    }
+}
+
+fn despawn_character_select(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<CharacterSelectScreen>>,
+) {
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,297 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::{HashMap, HashSet};
+use bevy_asset_loader::prelude::*;
+use serde::Deserialize;
+
+use crate::collider::{Collider, OverlapStarted};
+use crate::health::{Health, Invulnerable};
+use crate::helpers::json::JsonAssetPlugin;
+use crate::offscreen::{DespawnWhenOffscreen, Pool};
+use crate::stars::Score;
+use crate::timestep::SimulationSet;
+use crate::GameState;
+use crate::Player;
+
+/// Pickups the player collects by touching them, each kind described in
+/// `default.collectibles.json`.
+///
+/// Picking one up sends [`Collected`] and then applies the kind's effects, so a new pickup only
+/// needs an entry in the registry.
+pub struct CollectiblePlugin;
+
+impl Plugin for CollectiblePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(JsonAssetPlugin::<CollectibleRegistry>::new(&[
+            "collectibles.json",
+        ]));
+
+        app.init_collection::<CollectibleAssets>()
+            .init_resource::<Inventory>()
+            .init_resource::<PowerUps>()
+            .add_event::<Collected>()
+            .add_systems(
+                FixedUpdate,
+                (collect_pickups, apply_pickup_effects, shield_player)
+                    .chain()
+                    .in_set(SimulationSet::Collision)
+                    .run_if(in_state(GameState::Running)),
+            )
+            .add_systems(
+                FixedUpdate,
+                tick_power_ups.run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
+/// Every kind of pickup, keyed by name. Read whenever one spawns or is collected.
+#[derive(Deserialize, TypeUuid, TypePath, Debug)]
+#[uuid = "b7e2c4f1-6a3d-4e58-9c10-2f8d5a7b3e64"]
+pub struct CollectibleRegistry {
+    pub kinds: HashMap<String, CollectibleKind>,
+}
+
+impl CollectibleRegistry {
+    pub fn get(&self, kind: &str) -> Option<&CollectibleKind> {
+        self.kinds.get(kind)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CollectibleKind {
+    /// Sprite path relative to `assets`, tinted with `color`.
+    pub sprite: String,
+    #[serde(default = "default_color")]
+    pub color: [f32; 3],
+    /// Width and height of the sprite and diameter of the collider, in pixels.
+    pub size: f32,
+    /// Played when picked up.
+    #[serde(default)]
+    pub sound: Option<String>,
+    /// Applied in order to whoever picks it up.
+    #[serde(default)]
+    pub effects: Vec<PickupEffect>,
+}
+
+fn default_color() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+/// What picking something up does.
+#[derive(Deserialize, Clone, Debug)]
+pub enum PickupEffect {
+    /// Points added to the [`Score`].
+    Score(u32),
+    /// Health restored to the collector, up to their maximum.
+    Heal(u32),
+    /// Added to the [`Inventory`]'s currency.
+    Currency(u32),
+    /// Turns on the named power-up in [`PowerUps`] for `duration` seconds, or extends it.
+    PowerUp { name: String, duration: f32 },
+    /// Adds the named key to the [`Inventory`].
+    Key(String),
+}
+
+/// Power-up that keeps the player [`Invulnerable`] while it lasts.
+pub const SHIELD_POWER_UP: &str = "shield";
+
+#[derive(AssetCollection, Resource)]
+pub struct CollectibleAssets {
+    #[asset(path = "config/default.collectibles.json")]
+    pub registry: Handle<CollectibleRegistry>,
+}
+
+/// Something the player can pick up, of a kind named in the registry.
+#[derive(Component, Clone, Debug)]
+pub struct Collectible {
+    pub kind: String,
+}
+
+/// Sent when `collector` picks up a collectible of `kind`, before its effects are applied.
+#[derive(Event, Clone, Debug)]
+pub struct Collected {
+    pub collector: Entity,
+    pub kind: String,
+}
+
+/// What the player has picked up besides points and health.
+#[derive(Resource, Default, Debug)]
+pub struct Inventory {
+    pub currency: u32,
+    /// Names of the keys picked up so far. Each key is only held once.
+    pub keys: HashSet<String>,
+}
+
+/// Power-ups that are on, with the seconds each has left.
+#[derive(Resource, Default, Debug)]
+pub struct PowerUps {
+    remaining: HashMap<String, f32>,
+}
+
+impl PowerUps {
+    /// Seconds the named power-up has left, if it is on.
+    pub fn remaining(&self, name: &str) -> Option<f32> {
+        self.remaining.get(name).copied()
+    }
+
+    fn activate(&mut self, name: &str, duration: f32) {
+        let remaining = self.remaining.entry(name.to_string()).or_insert(0.0);
+        *remaining = remaining.max(duration);
+    }
+}
+
+/// Spawns a collectible of `kind` at `position`, or nothing if the registry doesn't know it.
+pub fn spawn_collectible(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    pool: &mut Pool<Collectible>,
+    registry: &CollectibleRegistry,
+    kind: &str,
+    position: Vec2,
+) -> Option<Entity> {
+    let Some(description) = registry.get(kind) else {
+        warn!("Tried to spawn an unknown collectible {:?}", kind);
+        return None;
+    };
+
+    let [r, g, b] = description.color;
+    let collectible = pool.spawn(
+        commands,
+        (
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(r, g, b),
+                    custom_size: Some(Vec2::splat(description.size)),
+                    ..default()
+                },
+                transform: Transform::from_translation(position.extend(0.0)),
+                texture: asset_server.load(&description.sprite),
+                ..default()
+            },
+            Collectible {
+                kind: kind.to_string(),
+            },
+            Collider::Circle {
+                radius: description.size / 2.0,
+            },
+            DespawnWhenOffscreen::default(),
+        ),
+    );
+    Some(collectible)
+}
+
+pub fn collect_pickups(
+    mut commands: Commands,
+    mut overlap_events: EventReader<OverlapStarted>,
+    mut collected_events: EventWriter<Collected>,
+    asset_server: Res<AssetServer>,
+    collectible_assets: Res<CollectibleAssets>,
+    registries: Res<Assets<CollectibleRegistry>>,
+    mut collectible_pool: ResMut<Pool<Collectible>>,
+    player_query: Query<(), With<Player>>,
+    is_collectible: Query<(), With<Collectible>>,
+    collectible_query: Query<&Collectible>,
+) {
+    let registry = registries.get(&collectible_assets.registry);
+
+    for OverlapStarted(pair) in overlap_events.iter() {
+        let Some((player, entity)) = pair.matching(&player_query, &is_collectible) else {
+            continue;
+        };
+        let Ok(collectible) = collectible_query.get(entity) else {
+            continue;
+        };
+
+        let sound = registry
+            .and_then(|registry| registry.get(&collectible.kind))
+            .and_then(|kind| kind.sound.as_ref());
+        if let Some(sound) = sound {
+            commands.spawn(AudioBundle {
+                source: asset_server.load(sound),
+                settings: PlaybackSettings::DESPAWN,
+            });
+        }
+
+        collected_events.send(Collected {
+            collector: player,
+            kind: collectible.kind.clone(),
+        });
+        collectible_pool.release(&mut commands, entity);
+    }
+}
+
+pub fn apply_pickup_effects(
+    mut collected_events: EventReader<Collected>,
+    collectible_assets: Res<CollectibleAssets>,
+    registries: Res<Assets<CollectibleRegistry>>,
+    mut score: ResMut<Score>,
+    mut inventory: ResMut<Inventory>,
+    mut power_ups: ResMut<PowerUps>,
+    mut health_query: Query<&mut Health>,
+) {
+    let Some(registry) = registries.get(&collectible_assets.registry) else {
+        return;
+    };
+
+    for collected in collected_events.iter() {
+        let Some(kind) = registry.get(&collected.kind) else {
+            continue;
+        };
+        for effect in kind.effects.iter() {
+            match effect {
+                PickupEffect::Score(points) => score.value += points,
+                PickupEffect::Heal(amount) => {
+                    if let Ok(mut health) = health_query.get_mut(collected.collector) {
+                        health.current = (health.current + amount).min(health.max);
+                    }
+                }
+                PickupEffect::Currency(amount) => inventory.currency += amount,
+                PickupEffect::PowerUp { name, duration } => power_ups.activate(name, *duration),
+                PickupEffect::Key(name) => {
+                    inventory.keys.insert(name.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Keeps the player invulnerable for as long as the shield lasts, extending any invulnerability
+/// they already have rather than cutting it short.
+fn shield_player(
+    mut commands: Commands,
+    power_ups: Res<PowerUps>,
+    mut player_query: Query<(Entity, Option<&mut Invulnerable>), With<Player>>,
+) {
+    let Some(remaining) = power_ups.remaining(SHIELD_POWER_UP) else {
+        return;
+    };
+
+    for (entity, invulnerable) in player_query.iter_mut() {
+        match invulnerable {
+            Some(mut invulnerable) => {
+                if invulnerable.timer.remaining_secs() < remaining {
+                    // Lengthened rather than restarted, so the blink carries on where it was
+                    let duration = invulnerable.timer.elapsed_secs() + remaining;
+                    invulnerable
+                        .timer
+                        .set_duration(Duration::from_secs_f32(duration));
+                }
+            }
+            None => {
+                commands.entity(entity).insert(Invulnerable {
+                    timer: Timer::from_seconds(remaining, TimerMode::Once),
+                });
+            }
+        }
+    }
+}
+
+fn tick_power_ups(fixed_time: Res<FixedTime>, mut power_ups: ResMut<PowerUps>) {
+    let delta_seconds = fixed_time.period.as_secs_f32();
+    power_ups.remaining.retain(|_, remaining| {
+        *remaining -= delta_seconds;
+        *remaining > 0.0
+    });
+}
@@ -0,0 +1,211 @@
+use bevy::ecs::query::ReadOnlyWorldQuery;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use smallvec::SmallVec;
+
+use crate::collision::Aabb;
+use crate::timestep::SimulationSet;
+
+/// Side length of a spatial hash cell. Roughly the size of the largest common collider.
+pub const SPATIAL_HASH_CELL_SIZE: f32 = 64.0;
+
+pub struct ColliderPlugin;
+
+impl Plugin for ColliderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialHash>()
+            .add_event::<OverlapStarted>()
+            .add_systems(
+                FixedUpdate,
+                update_spatial_hash.in_set(SimulationSet::Broadphase),
+            );
+    }
+}
+
+/// Shape used for overlap checks between gameplay entities, centred on the entity's translation.
+#[derive(Component, Clone, Copy, Debug)]
+pub enum Collider {
+    Circle { radius: f32 },
+    Aabb { half_extents: Vec2 },
+}
+
+impl Collider {
+    pub fn bounds(&self, position: Vec2) -> Aabb {
+        match *self {
+            Collider::Circle { radius } => {
+                Aabb::from_center_size(position, Vec2::splat(radius * 2.0))
+            }
+            Collider::Aabb { half_extents } => Aabb::from_center_size(position, half_extents * 2.0),
+        }
+    }
+
+    pub fn overlaps(&self, position: Vec2, other: &Collider, other_position: Vec2) -> bool {
+        match (*self, *other) {
+            (
+                Collider::Circle { radius },
+                Collider::Circle {
+                    radius: other_radius,
+                },
+            ) => position.distance_squared(other_position) < (radius + other_radius).powi(2),
+            (Collider::Circle { radius }, Collider::Aabb { half_extents }) => {
+                circle_overlaps_aabb(position, radius, other_position, half_extents)
+            }
+            (Collider::Aabb { half_extents }, Collider::Circle { radius }) => {
+                circle_overlaps_aabb(other_position, radius, position, half_extents)
+            }
+            (Collider::Aabb { .. }, Collider::Aabb { .. }) => self
+                .bounds(position)
+                .intersects(&other.bounds(other_position)),
+        }
+    }
+}
+
+fn circle_overlaps_aabb(center: Vec2, radius: f32, box_center: Vec2, half_extents: Vec2) -> bool {
+    let closest = center.clamp(box_center - half_extents, box_center + half_extents);
+    center.distance_squared(closest) < radius * radius
+}
+
+/// Two entities whose colliders overlap, stored with the lower entity first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OverlapPair {
+    pub a: Entity,
+    pub b: Entity,
+}
+
+impl OverlapPair {
+    pub fn new(a: Entity, b: Entity) -> Self {
+        if a < b {
+            OverlapPair { a, b }
+        } else {
+            OverlapPair { a: b, b: a }
+        }
+    }
+
+    /// Returns the pair ordered as `(first, second)` if one entity matches each query.
+    pub fn matching<F1: ReadOnlyWorldQuery, F2: ReadOnlyWorldQuery>(
+        &self,
+        first: &Query<(), F1>,
+        second: &Query<(), F2>,
+    ) -> Option<(Entity, Entity)> {
+        if first.contains(self.a) && second.contains(self.b) {
+            Some((self.a, self.b))
+        } else if first.contains(self.b) && second.contains(self.a) {
+            Some((self.b, self.a))
+        } else {
+            None
+        }
+    }
+}
+
+/// Sent on the tick two colliders start overlapping.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct OverlapStarted(pub OverlapPair);
+
+/// Uniform grid of collider entities, rebuilt every simulation tick.
+#[derive(Resource)]
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<Entity>>,
+    overlaps: HashSet<OverlapPair>,
+    /// `overlaps` by entity, so looking up one entity's overlaps doesn't scan every pair.
+    neighbours: HashMap<Entity, SmallVec<[Entity; 4]>>,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        SpatialHash {
+            cell_size: SPATIAL_HASH_CELL_SIZE,
+            cells: HashMap::default(),
+            overlaps: HashSet::default(),
+            neighbours: HashMap::default(),
+        }
+    }
+}
+
+impl SpatialHash {
+    fn cell_range(&self, bounds: Aabb) -> (IVec2, IVec2) {
+        (
+            (bounds.min / self.cell_size).floor().as_ivec2(),
+            (bounds.max / self.cell_size).floor().as_ivec2(),
+        )
+    }
+
+    fn insert(&mut self, entity: Entity, bounds: Aabb) {
+        let (min, max) = self.cell_range(bounds);
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(entity);
+            }
+        }
+    }
+
+    /// Fills `candidates` with the entities whose cells touch `bounds`. Candidates only; shapes
+    /// still need an exact test. The set is cleared first, so callers can keep reusing one.
+    pub fn candidates(&self, bounds: Aabb, candidates: &mut HashSet<Entity>) {
+        let (min, max) = self.cell_range(bounds);
+        candidates.clear();
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                if let Some(entities) = self.cells.get(&IVec2::new(x, y)) {
+                    candidates.extend(entities.iter().copied());
+                }
+            }
+        }
+    }
+
+    /// Every overlapping pair as of the last tick.
+    pub fn pairs(&self) -> impl Iterator<Item = OverlapPair> + '_ {
+        self.overlaps.iter().copied()
+    }
+
+    /// Everything overlapping `entity` as of the last tick.
+    pub fn overlapping(&self, entity: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.neighbours.get(&entity).into_iter().flatten().copied()
+    }
+}
+
+pub fn update_spatial_hash(
+    mut spatial_hash: ResMut<SpatialHash>,
+    collider_query: Query<(Entity, &Transform, &Collider)>,
+    mut overlap_started: EventWriter<OverlapStarted>,
+    mut candidates: Local<HashSet<Entity>>,
+) {
+    spatial_hash.cells.clear();
+    for (entity, transform, collider) in collider_query.iter() {
+        spatial_hash.insert(entity, collider.bounds(transform.translation.truncate()));
+    }
+
+    let mut overlaps = HashSet::default();
+    for (entity, transform, collider) in collider_query.iter() {
+        let position = transform.translation.truncate();
+        spatial_hash.candidates(collider.bounds(position), &mut candidates);
+        for &other in candidates.iter() {
+            // Each pair is tested once, from its lower entity
+            if other <= entity {
+                continue;
+            }
+            let Ok((_, other_transform, other_collider)) = collider_query.get(other) else {
+                continue;
+            };
+            if collider.overlaps(
+                position,
+                other_collider,
+                other_transform.translation.truncate(),
+            ) {
+                overlaps.insert(OverlapPair::new(entity, other));
+            }
+        }
+    }
+
+    for pair in overlaps.difference(&spatial_hash.overlaps) {
+        overlap_started.send(OverlapStarted(*pair));
+    }
+
+    let neighbours = &mut spatial_hash.neighbours;
+    neighbours.clear();
+    for pair in overlaps.iter() {
+        neighbours.entry(pair.a).or_default().push(pair.b);
+        neighbours.entry(pair.b).or_default().push(pair.a);
+    }
+    spatial_hash.overlaps = overlaps;
+}
@@ -0,0 +1,467 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_ecs_ldtk::prelude::*;
+
+/// Size of a single LDtk IntGrid cell in world units.
+pub const TILE_SIZE: f32 = 16.0;
+
+/// IntGrid values of the terrain cells in `BasicLevel.ldtk`.
+pub const GROUND_INT_GRID_VALUE: i32 = 1;
+pub const ONE_WAY_INT_GRID_VALUE: i32 = 4;
+pub const SLOPE_UP_45_INT_GRID_VALUE: i32 = 5;
+pub const SLOPE_DOWN_45_INT_GRID_VALUE: i32 = 6;
+pub const SLOPE_UP_22_LOW_INT_GRID_VALUE: i32 = 7;
+pub const SLOPE_UP_22_HIGH_INT_GRID_VALUE: i32 = 8;
+pub const SLOPE_DOWN_22_HIGH_INT_GRID_VALUE: i32 = 9;
+pub const SLOPE_DOWN_22_LOW_INT_GRID_VALUE: i32 = 10;
+
+const TERRAIN_INT_GRID_VALUES: [i32; 8] = [
+    GROUND_INT_GRID_VALUE,
+    ONE_WAY_INT_GRID_VALUE,
+    SLOPE_UP_45_INT_GRID_VALUE,
+    SLOPE_DOWN_45_INT_GRID_VALUE,
+    SLOPE_UP_22_LOW_INT_GRID_VALUE,
+    SLOPE_UP_22_HIGH_INT_GRID_VALUE,
+    SLOPE_DOWN_22_HIGH_INT_GRID_VALUE,
+    SLOPE_DOWN_22_LOW_INT_GRID_VALUE,
+];
+
+/// How far above its feet a body still finds the ground, so it can follow slopes up and down.
+pub const STEP_HEIGHT: f32 = TILE_SIZE / 2.0;
+
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TerrainColliders>()
+            .add_systems(Update, rebuild_terrain_colliders);
+
+        for value in TERRAIN_INT_GRID_VALUES {
+            app.register_ldtk_int_cell::<TerrainTileBundle>(value);
+        }
+    }
+}
+
+/// Axis aligned bounding box in world space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Aabb {
+    pub fn from_center_size(center: Vec2, size: Vec2) -> Self {
+        Aabb {
+            min: center - size / 2.0,
+            max: center + size / 2.0,
+        }
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x < other.max.x
+            && self.max.x > other.min.x
+            && self.min.y < other.max.y
+            && self.max.y > other.min.y
+    }
+
+    /// Smallest box containing both `self` and `other`.
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// The box grown by `amount` on every side.
+    pub fn expanded(&self, amount: f32) -> Aabb {
+        Aabb {
+            min: self.min - Vec2::splat(amount),
+            max: self.max + Vec2::splat(amount),
+        }
+    }
+
+    pub fn translated(&self, offset: Vec2) -> Aabb {
+        Aabb {
+            min: self.min + offset,
+            max: self.max + offset,
+        }
+    }
+}
+
+/// Gap left between a body and whatever it was swept into, so resting contacts stay detectable.
+const SKIN_WIDTH: f32 = 0.01;
+
+/// Outcome of moving a body through the terrain.
+#[derive(Clone, Debug, Default)]
+pub struct Sweep {
+    /// How far the box could actually move.
+    pub translation: Vec2,
+    /// Surface normals of everything the box ran into.
+    pub normals: Vec<Vec2>,
+}
+
+/// Moves `aabb` by `motion`, resolving the x axis and then the y axis against `cells`.
+///
+/// Each axis is tested against the full swept range, so fast movers stop at the first surface in
+/// their path instead of tunnelling through it. Only solid cells block sideways movement; on the
+/// way down the body rests on the highest floor under it, which for slopes is the surface height
+/// under its centre. `grounded` bodies also snap down onto floors within [`STEP_HEIGHT`] so they
+/// stay on descending slopes instead of bouncing down them.
+pub fn move_body(
+    aabb: Aabb,
+    motion: Vec2,
+    cells: &[TerrainCell],
+    grounded: bool,
+    drop_through: bool,
+) -> Sweep {
+    let mut sweep = Sweep::default();
+
+    // Solid tiles continuing the top of a slope are walked onto, not into
+    let walls: Vec<Aabb> = cells
+        .iter()
+        .filter(|cell| cell.kind == TerrainKind::Solid && !cell.is_slope_lip(cells, motion.x))
+        .map(|cell| cell.aabb)
+        .collect();
+    let (distance_x, hit_wall) = sweep_axis(&aabb, motion.x, 0, &walls);
+    let moved = aabb.translated(Vec2::new(distance_x, 0.0));
+    sweep.translation.x = distance_x;
+    if hit_wall {
+        sweep.normals.push(Vec2::new(-motion.x.signum(), 0.0));
+    }
+
+    if motion.y > 0.0 {
+        let ceilings: Vec<Aabb> = cells
+            .iter()
+            .filter(|cell| cell.kind == TerrainKind::Solid)
+            .map(|cell| cell.aabb)
+            .collect();
+        let (distance_y, hit_ceiling) = sweep_axis(&moved, motion.y, 1, &ceilings);
+        sweep.translation.y = distance_y;
+        if hit_ceiling {
+            sweep.normals.push(Vec2::NEG_Y);
+        }
+        return sweep;
+    }
+
+    // A body moving sideways can climb or descend a slope by as much as it moved
+    let climb = STEP_HEIGHT + distance_x.abs();
+    let reach = if grounded { climb } else { 0.0 };
+
+    match find_floor(&moved, cells, climb, aabb.min.y, drop_through) {
+        Some((height, normal)) if height >= moved.min.y + motion.y - reach => {
+            sweep.translation.y = height - moved.min.y;
+            sweep.normals.push(normal);
+        }
+        _ => sweep.translation.y = motion.y,
+    }
+
+    sweep
+}
+
+/// Highest surface under `aabb` no more than `climb` above its feet, with the surface normal.
+fn find_floor(
+    aabb: &Aabb,
+    cells: &[TerrainCell],
+    climb: f32,
+    previous_bottom: f32,
+    drop_through: bool,
+) -> Option<(f32, Vec2)> {
+    let center_x = (aabb.min.x + aabb.max.x) / 2.0;
+
+    // Slopes are sampled under the body's centre and take priority over the tiles around them
+    let slope = highest(cells.iter().filter_map(|cell| {
+        let TerrainKind::Slope { left, right } = cell.kind else {
+            return None;
+        };
+        if center_x < cell.aabb.min.x || center_x > cell.aabb.max.x {
+            return None;
+        }
+        let height = cell.surface_at(center_x);
+        (height <= aabb.min.y + climb).then(|| (height, Vec2::new(left - right, 1.0).normalize()))
+    }));
+    if slope.is_some() {
+        return slope;
+    }
+
+    highest(cells.iter().filter_map(|cell| {
+        if aabb.min.x >= cell.aabb.max.x || aabb.max.x <= cell.aabb.min.x {
+            return None;
+        }
+        let height = cell.aabb.max.y;
+        let is_floor = match cell.kind {
+            TerrainKind::Solid => height <= aabb.min.y + climb,
+            // One-way platforms only catch bodies that were above them before this step
+            TerrainKind::OneWay => !drop_through && height <= previous_bottom + SKIN_WIDTH,
+            TerrainKind::Slope { .. } => false,
+        };
+        is_floor.then_some((height, Vec2::Y))
+    }))
+}
+
+fn highest(floors: impl Iterator<Item = (f32, Vec2)>) -> Option<(f32, Vec2)> {
+    floors.max_by(|(a, _), (b, _)| a.total_cmp(b))
+}
+
+fn sweep_axis(aabb: &Aabb, distance: f32, axis: usize, colliders: &[Aabb]) -> (f32, bool) {
+    let other_axis = 1 - axis;
+    let mut allowed = distance;
+    let mut hit = false;
+
+    for collider in colliders {
+        // Only colliders that overlap on the other axis can be run into
+        if aabb.min[other_axis] >= collider.max[other_axis]
+            || aabb.max[other_axis] <= collider.min[other_axis]
+        {
+            continue;
+        }
+
+        if distance > 0.0 && collider.min[axis] >= aabb.max[axis] - SKIN_WIDTH {
+            let gap = (collider.min[axis] - aabb.max[axis] - SKIN_WIDTH).max(0.0);
+            if gap <= allowed {
+                allowed = gap;
+                hit = true;
+            }
+        } else if distance < 0.0 && collider.max[axis] <= aabb.min[axis] + SKIN_WIDTH {
+            let gap = (collider.max[axis] - aabb.min[axis] + SKIN_WIDTH).min(0.0);
+            if gap >= allowed {
+                allowed = gap;
+                hit = true;
+            }
+        }
+    }
+
+    (allowed, hit)
+}
+
+/// Marks an entity as moved by [`move_body`] in the physics step instead of free integration.
+#[derive(Component)]
+pub struct KinematicBody {
+    pub size: Vec2,
+    /// Seconds left during which one-way platforms don't catch this body.
+    pub drop_through: f32,
+}
+
+impl KinematicBody {
+    pub fn new(size: Vec2) -> Self {
+        KinematicBody {
+            size,
+            drop_through: 0.0,
+        }
+    }
+}
+
+/// Contact normals produced by the last physics step for a [`KinematicBody`].
+#[derive(Component, Default, Debug)]
+pub struct Contacts {
+    pub normals: Vec<Vec2>,
+}
+
+impl Contacts {
+    pub fn on_ground(&self) -> bool {
+        self.normals.iter().any(|normal| normal.y > 0.5)
+    }
+}
+
+/// How a terrain cell collides, mapped from its LDtk IntGrid value.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum TerrainKind {
+    #[default]
+    Solid,
+    /// Only collides from above; can be jumped up through and dropped down through.
+    OneWay,
+    /// Walkable ramp, with the surface height at the cell's left and right edges as a fraction of
+    /// the cell height.
+    Slope { left: f32, right: f32 },
+}
+
+impl TerrainKind {
+    pub fn from_int_grid_value(value: i32) -> TerrainKind {
+        match value {
+            ONE_WAY_INT_GRID_VALUE => TerrainKind::OneWay,
+            SLOPE_UP_45_INT_GRID_VALUE => TerrainKind::Slope {
+                left: 0.0,
+                right: 1.0,
+            },
+            SLOPE_DOWN_45_INT_GRID_VALUE => TerrainKind::Slope {
+                left: 1.0,
+                right: 0.0,
+            },
+            SLOPE_UP_22_LOW_INT_GRID_VALUE => TerrainKind::Slope {
+                left: 0.0,
+                right: 0.5,
+            },
+            SLOPE_UP_22_HIGH_INT_GRID_VALUE => TerrainKind::Slope {
+                left: 0.5,
+                right: 1.0,
+            },
+            SLOPE_DOWN_22_HIGH_INT_GRID_VALUE => TerrainKind::Slope {
+                left: 1.0,
+                right: 0.5,
+            },
+            SLOPE_DOWN_22_LOW_INT_GRID_VALUE => TerrainKind::Slope {
+                left: 0.5,
+                right: 0.0,
+            },
+            _ => TerrainKind::Solid,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Default, Component)]
+pub struct TerrainTile {
+    pub kind: TerrainKind,
+}
+
+impl From<IntGridCell> for TerrainTile {
+    fn from(int_grid_cell: IntGridCell) -> Self {
+        TerrainTile {
+            kind: TerrainKind::from_int_grid_value(int_grid_cell.value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
+pub struct TerrainTileBundle {
+    #[from_int_grid_cell]
+    terrain_tile: TerrainTile,
+}
+
+/// A terrain tile's world space box and collision behaviour.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TerrainCell {
+    pub aabb: Aabb,
+    pub kind: TerrainKind,
+}
+
+impl TerrainCell {
+    /// Height of the walkable surface at `x`.
+    pub fn surface_at(&self, x: f32) -> f32 {
+        match self.kind {
+            TerrainKind::Slope { left, right } => {
+                let size = self.aabb.max - self.aabb.min;
+                let t = ((x - self.aabb.min.x) / size.x).clamp(0.0, 1.0);
+                self.aabb.min.y + (left + (right - left) * t) * size.y
+            }
+            TerrainKind::Solid | TerrainKind::OneWay => self.aabb.max.y,
+        }
+    }
+
+    /// Whether this cell sits level with the high end of a slope that a body moving in
+    /// `direction` along x is walking up from.
+    fn is_slope_lip(&self, cells: &[TerrainCell], direction: f32) -> bool {
+        const TOLERANCE: f32 = 0.5;
+        cells.iter().any(|other| match other.kind {
+            TerrainKind::Slope { left, right } => {
+                let level = (other.aabb.max.y - self.aabb.max.y).abs() < TOLERANCE;
+                let rises_from_left = direction > 0.0
+                    && right >= 1.0
+                    && (other.aabb.max.x - self.aabb.min.x).abs() < TOLERANCE;
+                let rises_from_right = direction < 0.0
+                    && left >= 1.0
+                    && (other.aabb.min.x - self.aabb.max.x).abs() < TOLERANCE;
+                level && (rises_from_left || rises_from_right)
+            }
+            TerrainKind::Solid | TerrainKind::OneWay => false,
+        })
+    }
+}
+
+/// World space boxes of every terrain tile, bucketed by cell so lookups only touch nearby tiles.
+#[derive(Resource, Default)]
+pub struct TerrainColliders {
+    tiles: HashMap<IVec2, TerrainCell>,
+}
+
+impl TerrainColliders {
+    /// The grid cell containing `position`.
+    pub fn cell(position: Vec2) -> IVec2 {
+        (position / TILE_SIZE).floor().as_ivec2()
+    }
+
+    /// World space centre of `cell`.
+    pub fn cell_center(cell: IVec2) -> Vec2 {
+        (cell.as_vec2() + 0.5) * TILE_SIZE
+    }
+
+    /// Looking down the column at `x` from `top` to `bottom`, the first open cell sitting on a tile.
+    pub fn open_cell_above_ground(&self, x: f32, top: f32, bottom: f32) -> Option<IVec2> {
+        let top = Self::cell(Vec2::new(x, top));
+        let bottom = Self::cell(Vec2::new(x, bottom)).y;
+        (bottom..top.y)
+            .rev()
+            .map(|y| IVec2::new(top.x, y))
+            .find(|&cell| {
+                self.tiles.contains_key(&cell) && !self.tiles.contains_key(&(cell + IVec2::Y))
+            })
+            .map(|cell| cell + IVec2::Y)
+    }
+
+    /// The tile in `cell`, if there is one.
+    pub fn tile(&self, cell: IVec2) -> Option<TerrainCell> {
+        self.tiles.get(&cell).copied()
+    }
+
+    /// Every tile with the grid cell it is in.
+    pub fn tiles(&self) -> impl Iterator<Item = (IVec2, TerrainCell)> + '_ {
+        self.tiles.iter().map(|(cell, tile)| (*cell, *tile))
+    }
+
+    /// Terrain cells intersecting `area`.
+    pub fn overlapping(&self, area: Aabb) -> impl Iterator<Item = TerrainCell> + '_ {
+        // Tile centres are not guaranteed to sit on the cell grid, so widen the search by a cell.
+        let min = Self::cell(area.min) - IVec2::ONE;
+        let max = Self::cell(area.max) + IVec2::ONE;
+
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .filter_map(|cell| self.tiles.get(&cell).copied())
+            .filter(move |tile| tile.aabb.intersects(&area))
+    }
+
+    /// Whether `aabb` is standing only on one-way platforms.
+    pub fn is_one_way_under(&self, aabb: Aabb) -> bool {
+        let feet = Aabb {
+            min: Vec2::new(aabb.min.x, aabb.min.y - 1.0),
+            max: Vec2::new(aabb.max.x, aabb.min.y),
+        };
+        let mut kinds = self.overlapping(feet).map(|cell| cell.kind).peekable();
+        kinds.peek().is_some() && kinds.all(|kind| kind == TerrainKind::OneWay)
+    }
+
+    /// Whether the straight line from `from` to `to` passes no solid tile. One-way platforms and
+    /// slopes don't block sight.
+    pub fn line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        // Half a tile per step, so the line can't skip over a tile's corner
+        let steps = (from.distance(to) / (TILE_SIZE / 2.0)).ceil() as usize;
+        (0..=steps).all(|step| {
+            let point = from.lerp(to, step as f32 / steps.max(1) as f32);
+            self.overlapping(Aabb::from_center_size(point, Vec2::ONE))
+                .all(|tile| tile.kind != TerrainKind::Solid)
+        })
+    }
+}
+
+/// Rebuilds the tile lookup whenever LDtk spawns, moves or despawns terrain tiles.
+pub fn rebuild_terrain_colliders(
+    mut terrain: ResMut<TerrainColliders>,
+    changed_tiles: Query<(), (With<TerrainTile>, Changed<GlobalTransform>)>,
+    mut removed_tiles: RemovedComponents<TerrainTile>,
+    tile_query: Query<(&GlobalTransform, &TerrainTile)>,
+) {
+    let removed = removed_tiles.iter().count() > 0;
+    if changed_tiles.is_empty() && !removed {
+        return;
+    }
+
+    terrain.tiles.clear();
+    for (transform, tile) in tile_query.iter() {
+        let center = transform.translation().truncate();
+        terrain.tiles.insert(
+            TerrainColliders::cell(center),
+            TerrainCell {
+                aabb: Aabb::from_center_size(center, Vec2::splat(TILE_SIZE)),
+                kind: tile.kind,
+            },
+        );
+    }
+}
@@ -1,133 +1,264 @@
 use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
 use bevy::window::PrimaryWindow;
+use bevy_asset_loader::prelude::*;
 use rand::prelude::*;
-
+use serde::Deserialize;
+
+use crate::archetype::{EnemyArchetypes, EnemyAssets, EnemySounds};
+use crate::behaviour::BehaviourSet;
+use crate::collider::{Collider, SpatialHash};
+use crate::collision::{Aabb, Contacts, KinematicBody, TerrainColliders, TerrainKind, TILE_SIZE};
+use crate::health::{apply_damage, Damage, Died, Health, Invulnerable};
+use crate::helpers::json::JsonAssetPlugin;
+use crate::navigation::{feet, LinkKind, NavAgent};
+use crate::player::{Gravity, PlayerActionState, PlayerState, Velocity};
 use crate::prelude::*;
 use crate::stars::Score;
-use crate::GameOver;
+use crate::timestep::SimulationSet;
 use crate::GameState;
 use crate::Player;
 
 use bevy::app::App;
 
-use bevy::audio::PlaybackMode;
+use bevy::audio::{PlaybackMode, Volume};
 
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<EnemySpawnTimer>()
-            .add_systems(Startup, spawn_enemies)
-            .add_systems(
-                Update,
-                tick_enemy_spawn_timer.run_if(in_state(GameState::Running)),
-            )
+        app.add_plugins(JsonAssetPlugin::<EnemyArchetypes>::new(&["enemies.json"]));
+
+        app.init_collection::<EnemyAssets>()
+            .add_event::<EnemyStomped>()
             .add_systems(
-                Update,
-                spawn_enemies_over_time.run_if(in_state(GameState::Running)),
+                FixedUpdate,
+                (separate_enemies, (enemy_movement, walk_enemies))
+                    .chain()
+                    .after(BehaviourSet::Steer)
+                    .in_set(SimulationSet::Movement)
+                    .run_if(in_state(GameState::Running)),
             )
-            .add_systems(Update, enemy_movement.run_if(in_state(GameState::Running)))
             .add_systems(
-                Update,
-                handle_enemy_boundary.run_if(in_state(GameState::Running)),
+                FixedUpdate,
+                (
+                    bounce_enemies.before(handle_enemy_boundary),
+                    handle_enemy_boundary,
+                    enemy_hit_player,
+                )
+                    .in_set(SimulationSet::Collision)
+                    .run_if(in_state(GameState::Running)),
             )
             .add_systems(
-                Update,
-                enemy_hit_player.run_if(in_state(GameState::Running)),
+                FixedUpdate,
+                (despawn_dead_enemies, reward_stomps)
+                    .after(apply_damage)
+                    .in_set(SimulationSet::Damage)
+                    .run_if(in_state(GameState::Running)),
             );
     }
 }
 #[derive(Component)]
 pub struct Enemy {
+    /// Movement per tick as a fraction of `speed`, set by the enemy's behaviours.
     pub direction: Vec2,
+    /// Top speed in pixels per second.
+    pub speed: f32,
+    pub contact_damage: u32,
+    /// Points for stomping it.
+    pub score: u32,
+    pub sounds: EnemySounds,
 }
 
-#[derive(Resource)]
-pub struct EnemySpawnTimer {
-    pub timer: Timer,
+/// How an enemy gets along with the enemies around it.
+#[derive(Component, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Crowding {
+    /// Steers away from neighbours to keep a little space, so groups spread out instead of
+    /// stacking. Other enemies bounce off it.
+    #[default]
+    Separate,
+    /// Bounces off other enemies like a billiard ball.
+    Bounce,
 }
 
-impl Default for EnemySpawnTimer {
-    fn default() -> EnemySpawnTimer {
-        EnemySpawnTimer {
-            timer: Timer::from_seconds(ENEMY_SPAWN_TIME, TimerMode::Repeating),
-        }
+/// Moves enemies without a [`KinematicBody`] straight along their direction, through anything.
+pub fn enemy_movement(
+    mut enemy_query: Query<(&mut Transform, &Enemy), Without<KinematicBody>>,
+    fixed_time: Res<FixedTime>,
+) {
+    for (mut transform, enemy) in enemy_query.iter_mut() {
+        let direction = Vec3::new(enemy.direction.x, enemy.direction.y, 0.0);
+        transform.translation += direction * enemy.speed * fixed_time.period.as_secs_f32();
     }
 }
 
-pub fn spawn_enemies(
-    mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    asset_server: Res<AssetServer>,
-    player_query: Query<&Transform, With<Player>>,
+/// Walks enemies with a [`KinematicBody`] left or right along their direction, and jumps or drops
+/// through platforms where their path says to. `physics_system` then applies gravity and stops
+/// them at the terrain.
+pub fn walk_enemies(
+    gravity: Res<Gravity>,
+    mut enemy_query: Query<(
+        &Transform,
+        &Enemy,
+        &mut Velocity,
+        &mut KinematicBody,
+        &Contacts,
+        Option<&NavAgent>,
+    )>,
 ) {
-    let window = window_query.get_single().unwrap();
-
-    // Check if player exists and get its transform.
-    let player_transform = if let Ok(transform) = player_query.get_single() {
-        transform
-    } else {
-        return; // If player doesn't exist, exit the function early.
-    };
+    for (transform, enemy, mut velocity, mut body, contacts, agent) in enemy_query.iter_mut() {
+        velocity.value.x = enemy.direction.x * enemy.speed;
 
-    let player_position = player_transform.translation;
+        if !contacts.on_ground() {
+            continue;
+        }
+        let Some(next) = agent.and_then(|agent| agent.remaining().first()) else {
+            continue;
+        };
+        let offset = next.position - feet(transform.translation.truncate(), &body);
+        match next.kind {
+            LinkKind::Jump => {
+                // A cell over the landing, and higher for longer jumps to have time to get across
+                let height = offset.y.max(0.0) + TILE_SIZE.max(offset.x.abs() / 2.0);
+                velocity.value.y = (2.0 * -gravity.value * height).sqrt();
+            }
+            // Straight down means through the platform underfoot, not off a ledge
+            LinkKind::Drop if offset.x.abs() < TILE_SIZE / 2.0 => {
+                body.drop_through = DROP_THROUGH_TIME;
+            }
+            _ => {}
+        }
+    }
+}
 
-    const SAFE_ZONE_RADIUS: f32 = PLAYER_SIZE * 6.0; // You can adjust this as needed.
+/// Turns walkers away from the enemies close around them, harder the closer they are. Neighbours
+/// come from the spatial hash, so only nearby enemies are looked at.
+pub fn separate_enemies(
+    spatial_hash: Res<SpatialHash>,
+    mut enemy_query: Query<(Entity, &Transform, &Collider, &Crowding, &mut Enemy)>,
+    mut candidates: Local<HashSet<Entity>>,
+) {
+    let neighbours: HashMap<Entity, (Vec2, f32)> = enemy_query
+        .iter()
+        .map(|(entity, transform, collider, _, _)| {
+            let position = transform.translation.truncate();
+            let half_size = collider.bounds(Vec2::ZERO).max;
+            (entity, (position, half_size.max_element()))
+        })
+        .collect();
+
+    for (entity, transform, collider, crowding, mut enemy) in enemy_query.iter_mut() {
+        if *crowding != Crowding::Separate {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let area = collider.bounds(position).expanded(ENEMY_SEPARATION_GAP);
+        let (_, radius) = neighbours[&entity];
+
+        let mut push = Vec2::ZERO;
+        spatial_hash.candidates(area, &mut candidates);
+        for &other in candidates.iter() {
+            let Some(&(other_position, other_radius)) = neighbours.get(&other) else {
+                continue;
+            };
+            if other == entity {
+                continue;
+            }
+            let away = position - other_position;
+            let comfortable = radius + other_radius + ENEMY_SEPARATION_GAP;
+            let distance = away.length();
+            if distance >= comfortable {
+                continue;
+            }
+            // Exactly on top of each other: pick a side so they can come apart
+            let away = away.try_normalize().unwrap_or(Vec2::X);
+            push += away * (1.0 - distance / comfortable);
+        }
 
-    // Change the center to be based on player's X position
-    let center_x = player_position.x;
-    let center_y = window.height() / 2.0; // Keep vertical centering based on window
+        if push != Vec2::ZERO {
+            enemy.direction =
+                (enemy.direction + push * ENEMY_SEPARATION_WEIGHT).clamp_length_max(1.0);
+        }
+    }
+}
 
-    for _ in 0..NUMBER_OF_ENEMIES {
-        let mut random_x;
-        let mut random_y;
+/// Bounces ball-style enemies off the enemies they overlap, as an elastic collision between equal
+/// masses. Walkers they hit stay put and get out of the way by separating.
+pub fn bounce_enemies(
+    spatial_hash: Res<SpatialHash>,
+    mut enemy_query: Query<(&mut Transform, &Collider, &Crowding, &mut Enemy)>,
+) {
+    for pair in spatial_hash.pairs() {
+        let Ok([a, b]) = enemy_query.get_many_mut([pair.a, pair.b]) else {
+            continue;
+        };
+        let (mut a_transform, a_collider, a_crowding, mut a_enemy) = a;
+        let (mut b_transform, b_collider, b_crowding, mut b_enemy) = b;
+        let a_bounces = *a_crowding == Crowding::Bounce;
+        let b_bounces = *b_crowding == Crowding::Bounce;
+        if !a_bounces && !b_bounces {
+            continue;
+        }
 
-        loop {
-            random_x = center_x + (random::<f32>() * window.width() - window.width() / 2.0);
-            random_y = random::<f32>() * window.height();
-            let distance_to_center =
-                ((random_x - center_x).powi(2) + (random_y - center_y).powi(2)).sqrt();
+        let offset = a_transform.translation.truncate() - b_transform.translation.truncate();
+        let normal = offset.try_normalize().unwrap_or(Vec2::X);
+        let a_velocity = a_enemy.direction * a_enemy.speed;
+        let b_velocity = b_enemy.direction * b_enemy.speed;
+        let closing = (a_velocity - b_velocity).dot(normal);
+
+        // Push apart by the overlap, split between whoever bounces
+        let a_radius = a_collider.bounds(Vec2::ZERO).max.max_element();
+        let b_radius = b_collider.bounds(Vec2::ZERO).max.max_element();
+        let overlap = (a_radius + b_radius - offset.length()).max(0.0);
+        let (a_share, b_share) = match (a_bounces, b_bounces) {
+            (true, true) => (0.5, 0.5),
+            (true, false) => (1.0, 0.0),
+            _ => (0.0, 1.0),
+        };
+        a_transform.translation += (normal * overlap * a_share).extend(0.0);
+        b_transform.translation -= (normal * overlap * b_share).extend(0.0);
 
-            // If it's outside the safe zone, break the loop.
-            if distance_to_center > SAFE_ZONE_RADIUS {
-                break;
-            }
+        // Already moving apart
+        if closing >= 0.0 {
+            continue;
+        }
+        if a_bounces && b_bounces {
+            // Equal masses swap their velocities along the normal
+            set_velocity(&mut a_enemy, a_velocity - normal * closing);
+            set_velocity(&mut b_enemy, b_velocity + normal * closing);
+        } else if a_bounces {
+            set_velocity(&mut a_enemy, a_velocity - 2.0 * normal * closing);
+        } else {
+            set_velocity(&mut b_enemy, b_velocity + 2.0 * normal * closing);
         }
-
-        commands.spawn((
-            SpriteBundle {
-                transform: Transform::from_xyz(random_x, random_y, 0.0),
-                texture: asset_server.load("sprites/ball_red_large.png"),
-                ..default()
-            },
-            Enemy {
-                direction: Vec2::new(random::<f32>() * 2.0 - 1.0, random::<f32>() * 2.0 - 1.0)
-                    .normalize(),
-            },
-        ));
     }
 }
 
-pub fn enemy_movement(mut enemy_query: Query<(&mut Transform, &Enemy)>, time: Res<Time>) {
-    for (mut transform, enemy) in enemy_query.iter_mut() {
-        let direction = Vec3::new(enemy.direction.x, enemy.direction.y, 0.0);
-        transform.translation += direction * ENEMY_SPEED * time.delta_seconds();
+fn set_velocity(enemy: &mut Enemy, velocity: Vec2) {
+    if enemy.speed > 0.0 {
+        enemy.direction = (velocity / enemy.speed).clamp_length_max(1.0);
     }
 }
 
 pub fn handle_enemy_boundary(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut enemy_query: Query<(&mut Transform, &mut Enemy)>,
+    mut enemy_query: Query<(
+        &mut Transform,
+        &mut Enemy,
+        &Collider,
+        Option<&KinematicBody>,
+    )>,
     window_query: Query<&Window, With<PrimaryWindow>>,
 ) {
     let window = window_query.get_single().unwrap();
-    let half_enemy_size = ENEMY_SIZE / 2.0;
-    let x_min = 0.0 + half_enemy_size;
-    let y_min = 0.0 + half_enemy_size;
-    let y_max = window.height() - half_enemy_size;
 
-    for (mut transform, mut enemy) in enemy_query.iter_mut() {
+    for (mut transform, mut enemy, collider, body) in enemy_query.iter_mut() {
+        let half_enemy_size = collider.bounds(Vec2::ZERO).max;
+        let x_min = 0.0 + half_enemy_size.x;
+        let y_min = 0.0 + half_enemy_size.y;
+        let y_max = window.height() - half_enemy_size.y;
+
         let mut translation = transform.translation;
         let mut direction_changed = false;
 
@@ -138,12 +269,14 @@ pub fn handle_enemy_boundary(
             direction_changed = true;
         }
 
-        // Change direction for top and bottom boundaries
-        if translation.y < y_min {
+        // Change direction for top and bottom boundaries. Walkers are held up by the terrain and
+        // pulled down by gravity instead
+        let flies = body.is_none();
+        if flies && translation.y < y_min {
             translation.y = y_min;
             enemy.direction.y = enemy.direction.y.abs(); // Ensure positive Y direction
             direction_changed = true;
-        } else if translation.y > y_max {
+        } else if flies && translation.y > y_max {
             translation.y = y_max;
             enemy.direction.y = -enemy.direction.y.abs(); // Ensure negative Y direction
             direction_changed = true;
@@ -153,22 +286,14 @@ pub fn handle_enemy_boundary(
 
         // Play SFX if direction changed (i.e., boundary was hit)
         if direction_changed {
-            // Load Sound Effects
-            let sound_effects = [
-                asset_server.load("audio/pluck_001.ogg"),
-                asset_server.load("audio/pluck_002.ogg"),
-            ];
-
             // Randomly select a sound effect.
-            let selected_effect = if random::<f32>() > 0.5 {
-                sound_effects[0].clone()
-            } else {
-                sound_effects[1].clone()
+            let Some(selected_effect) = enemy.sounds.bounce.choose(&mut thread_rng()) else {
+                continue;
             };
 
             // Play the selected sound effect.
             commands.spawn(AudioBundle {
-                source: selected_effect,
+                source: asset_server.load(selected_effect),
                 settings: PlaybackSettings {
                     mode: PlaybackMode::Once,
                     ..default()
@@ -178,93 +303,189 @@ pub fn handle_enemy_boundary(
     }
 }
 
-pub fn tick_enemy_spawn_timer(mut enemy_spawn_timer: ResMut<EnemySpawnTimer>, time: Res<Time>) {
-    enemy_spawn_timer.timer.tick(time.delta());
+const SAFE_DISTANCE_FROM_PLAYER: f32 = PLAYER_SIZE * 3.0; // You can adjust this as needed.
+
+/// A random spot in `view`, away from the player, for a body `size` pixels across. The body's
+/// bottom cell is one `open` allows, and the rest of it must be clear of solid tiles. Without any,
+/// it goes just past the right of the view instead, standing on the ground there.
+pub fn random_spawn_position(
+    view: Aabb,
+    player_position: Option<Vec2>,
+    size: f32,
+    terrain: &TerrainColliders,
+    open: impl Fn(IVec2) -> bool,
+) -> Option<Vec2> {
+    // Without a player, keep the middle of the view clear instead
+    let center = (view.min + view.max) / 2.0;
+    let keep_clear = player_position.unwrap_or(center);
+
+    let position =
+        |cell: IVec2| TerrainColliders::cell_center(cell) + Vec2::Y * (size - TILE_SIZE) / 2.0;
+    let fits = |position: Vec2| {
+        terrain
+            .overlapping(Aabb::from_center_size(position, Vec2::splat(size)))
+            .all(|tile| tile.kind != TerrainKind::Solid)
+    };
+
+    let min = TerrainColliders::cell(view.min);
+    let max = TerrainColliders::cell(view.max);
+    let candidates: Vec<Vec2> = (min.y..=max.y)
+        .flat_map(|y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+        .filter(|&cell| open(cell))
+        .map(position)
+        .filter(|&position| {
+            position.distance(keep_clear) > SAFE_DISTANCE_FROM_PLAYER && fits(position)
+        })
+        .collect();
+    if let Some(&position) = candidates.choose(&mut thread_rng()) {
+        return Some(position);
+    }
+
+    let x = view.max.x + size;
+    let bottom = view.min.y - (view.max.y - view.min.y);
+    terrain
+        .open_cell_above_ground(x, view.max.y, bottom)
+        .map(position)
+        .filter(|&position| fits(position))
 }
 
-const SAFE_DISTANCE_FROM_PLAYER: f32 = PLAYER_SIZE * 3.0; // You can adjust this as needed.
+/// Sent when the player lands on an enemy. It only counts as defeated once the damage is applied.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct EnemyStomped {
+    pub enemy: Entity,
+}
 
-pub fn spawn_enemies_over_time(
+/// Landing on an enemy while falling defeats it and bounces the player up. Any other contact hurts
+/// the player.
+pub fn enemy_hit_player(
     mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    player_query: Query<&Transform, With<Player>>,
+    mut damage_events: EventWriter<Damage>,
+    mut stomped_events: EventWriter<EnemyStomped>,
+    spatial_hash: Res<SpatialHash>,
+    mut player_query: Query<
+        (
+            Entity,
+            &Transform,
+            &Collider,
+            &mut Velocity,
+            &mut PlayerState,
+            Option<&Invulnerable>,
+        ),
+        With<Player>,
+    >,
+    enemy_query: Query<(&Transform, &Health, &Enemy)>,
     asset_server: Res<AssetServer>,
-    enemy_spawn_timer: Res<EnemySpawnTimer>,
 ) {
-    if enemy_spawn_timer.timer.finished() {
-        let window = window_query.get_single().unwrap();
+    for (player_entity, player_transform, collider, mut velocity, mut player_state, invulnerable) in
+        player_query.iter_mut()
+    {
+        let feet = collider
+            .bounds(player_transform.translation.truncate())
+            .min
+            .y;
+
+        let mut side_hit = None;
+        let mut stomped = false;
+
+        // Checked every tick rather than on overlap start, so standing inside an enemy hurts again
+        // once the invulnerability wears off
+        for enemy_entity in spatial_hash.overlapping(player_entity) {
+            let Ok((enemy_transform, enemy_health, enemy)) = enemy_query.get(enemy_entity) else {
+                continue;
+            };
 
-        let (player_x, player_y) = if let Ok(player_transform) = player_query.get_single() {
-            (
-                player_transform.translation.x,
-                player_transform.translation.y,
-            )
-        } else {
-            // If the player doesn't exist, choose a default behavior.
-            // In this example, we're setting a default position, but you might want to just return and do nothing.
-            (window.width() / 2.0, window.height() / 2.0)
-        };
+            // Feet in the enemy's top half, so clipping its side just above the middle still hurts
+            let falling = velocity.value.y < 0.0;
+            let on_top = feet >= enemy_transform.translation.y;
+            if falling && on_top {
+                damage_events.send(Damage {
+                    target: enemy_entity,
+                    amount: enemy_health.current,
+                    source: player_transform.translation.truncate(),
+                    knockback: 0.0,
+                });
+                stomped_events.send(EnemyStomped {
+                    enemy: enemy_entity,
+                });
+                stomped = true;
+            } else if side_hit.is_none() {
+                side_hit = Some((enemy_transform.translation.truncate(), enemy));
+            }
+        }
 
-        let mut random_x;
-        let mut random_y;
+        if stomped {
+            velocity.value.y = PLAYER_STOMP_BOUNCE;
+            player_state.action_state = PlayerActionState::Jumping;
+            // Bouncing off one enemy into the side of another doesn't hurt
+            continue;
+        }
 
-        loop {
-            random_x = random::<f32>() * window.width();
-            random_y = random::<f32>() * window.height();
+        let Some((source, enemy)) = side_hit else {
+            continue;
+        };
+        if invulnerable.is_some() {
+            continue;
+        }
 
-            let distance_to_player =
-                ((random_x - player_x).powi(2) + (random_y - player_y).powi(2)).sqrt();
+        damage_events.send(Damage {
+            target: player_entity,
+            amount: enemy.contact_damage,
+            source,
+            knockback: PLAYER_KNOCKBACK,
+        });
 
-            // If it's outside the safe zone around the player or the default position, break the loop.
-            if distance_to_player > SAFE_DISTANCE_FROM_PLAYER {
-                break;
-            }
-        }
+        let Some(sound_effect) = &enemy.sounds.contact else {
+            continue;
+        };
 
-        commands.spawn((
-            SpriteBundle {
-                transform: Transform::from_xyz(random_x, random_y, 0.0),
-                texture: asset_server.load("sprites/ball_red_large.png"),
-                ..Default::default()
-            },
-            Enemy {
-                direction: Vec2::new(random::<f32>(), random::<f32>()).normalize(),
+        // Play the sound effect.
+        commands.spawn(AudioBundle {
+            source: asset_server.load(sound_effect),
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Despawn,
+                ..default()
             },
-        ));
+        });
     }
 }
 
-pub fn enemy_hit_player(
+/// Scores stomped enemies that the stomp actually defeated.
+pub fn reward_stomps(
     mut commands: Commands,
-    mut game_over_event_writer: EventWriter<GameOver>,
-    mut player_query: Query<(Entity, &Transform), With<Player>>,
-    enemy_query: Query<&Transform, With<Enemy>>,
+    mut stomped_events: EventReader<EnemyStomped>,
+    mut died_events: EventReader<Died>,
+    enemy_query: Query<&Enemy>,
     asset_server: Res<AssetServer>,
-    score: Res<Score>,
+    mut score: ResMut<Score>,
 ) {
-    if let Ok((player_entity, player_transform)) = player_query.get_single_mut() {
-        for enemy_transform in enemy_query.iter() {
-            let distance = player_transform
-                .translation
-                .distance(enemy_transform.translation);
-            let player_radius = PLAYER_SIZE / 2.0;
-            let enemy_radius = ENEMY_SIZE / 2.0;
-            if distance < player_radius + enemy_radius {
-                println!("Enemy hit player! Game Over!");
-                let sound_effect = asset_server.load("audio/explosionCrunch_000.ogg");
-
-                // Play the sound effect.
-                commands.spawn(AudioBundle {
-                    source: sound_effect,
-                    settings: PlaybackSettings {
-                        mode: PlaybackMode::Despawn,
-                        ..default()
-                    },
-                });
+    let died: HashSet<Entity> = died_events.iter().map(|died| died.entity).collect();
 
-                commands.entity(player_entity).despawn();
-                game_over_event_writer.send(GameOver { score: score.value });
-            }
+    for stomped in stomped_events.iter() {
+        if !died.contains(&stomped.enemy) {
+            continue;
+        }
+        let Ok(enemy) = enemy_query.get(stomped.enemy) else {
+            continue;
+        };
+        score.value += enemy.score;
+
+        if let Some(sound) = &enemy.sounds.defeated {
+            commands.spawn(AudioBundle {
+                source: asset_server.load(sound),
+                settings: PlaybackSettings::DESPAWN.with_volume(Volume::new_relative(0.8)),
+            });
+        }
+    }
+}
+
+pub fn despawn_dead_enemies(
+    mut commands: Commands,
+    mut died_events: EventReader<Died>,
+    enemy_query: Query<(), With<Enemy>>,
+) {
+    for died in died_events.iter() {
+        if enemy_query.contains(died.entity) {
+            commands.entity(died.entity).despawn();
         }
     }
 }
@@ -3,9 +3,13 @@ use bevy::prelude::*;
 #[derive(Component)]
 pub struct Platform {}
 
+// Only used by spawn_platforms, which is switched off in main while the levels come from LDtk
+#[allow(dead_code)]
 pub const PLATFORM_WIDTH: i32 = 1350;
+#[allow(dead_code)]
 pub const NUM_PLATFORMS: i32 = 100;
 
+#[allow(dead_code)]
 pub fn spawn_platforms(mut commands: Commands, asset_server: Res<AssetServer>) {
     // Spawn a platform as an example. You can add more.
     for i in 0..NUM_PLATFORMS {
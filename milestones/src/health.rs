@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::player::Velocity;
+use crate::timestep::SimulationSet;
+use crate::GameState;
+
+/// How long each on/off phase of the invulnerability blink lasts, in seconds.
+const BLINK_INTERVAL: f32 = 0.1;
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Damage>()
+            .add_event::<Died>()
+            .add_event::<LifeLost>()
+            .add_systems(
+                FixedUpdate,
+                (tick_invulnerability, apply_damage)
+                    .chain()
+                    .in_set(SimulationSet::Damage)
+                    .run_if(in_state(GameState::Running)),
+            )
+            .add_systems(
+                Update,
+                blink_invulnerable.run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
+/// Hit points shared by the player and enemies.
+///
+/// Running out of health spends a life and refills it; `Died` is only sent once no lives are left.
+#[derive(Component, Clone, Debug)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+    pub lives: u32,
+    /// Seconds the entity ignores damage for after being hit.
+    pub invulnerability_time: f32,
+}
+
+impl Health {
+    pub fn new(max: u32, lives: u32, invulnerability_time: f32) -> Self {
+        Health {
+            current: max,
+            max,
+            lives,
+            invulnerability_time,
+        }
+    }
+}
+
+/// Damage dealt to `target`, pushing it away from `source` if it has a velocity.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Damage {
+    pub target: Entity,
+    pub amount: u32,
+    pub source: Vec2,
+    pub knockback: f32,
+}
+
+/// Sent when an entity runs out of health with no lives left. Whoever owns it decides what happens.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Died {
+    pub entity: Entity,
+}
+
+/// Sent when an entity runs out of health and spends a life to refill it.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LifeLost {
+    pub entity: Entity,
+}
+
+/// Ignores damage until the timer runs out. Blinks the sprite while present.
+#[derive(Component)]
+pub struct Invulnerable {
+    pub timer: Timer,
+}
+
+pub fn apply_damage(
+    mut commands: Commands,
+    mut damage_events: EventReader<Damage>,
+    mut died_events: EventWriter<Died>,
+    mut life_lost_events: EventWriter<LifeLost>,
+    mut target_query: Query<
+        (&mut Health, &Transform, Option<&mut Velocity>),
+        Without<Invulnerable>,
+    >,
+) {
+    // Invulnerability is only inserted once commands apply, so guard against several hits this tick
+    let mut hit_this_tick = HashSet::default();
+
+    for damage in damage_events.iter() {
+        if !hit_this_tick.insert(damage.target) {
+            continue;
+        }
+        let Ok((mut health, transform, velocity)) = target_query.get_mut(damage.target) else {
+            continue;
+        };
+
+        health.current = health.current.saturating_sub(damage.amount);
+
+        if let Some(mut velocity) = velocity {
+            // Always hop up and away, even when hit from directly above or below
+            let away = transform.translation.x - damage.source.x;
+            let knockback = Vec2::new(away.signum(), 1.0).normalize() * damage.knockback;
+            velocity.value = knockback.extend(velocity.value.z);
+        }
+
+        if health.current == 0 {
+            if health.lives == 0 {
+                died_events.send(Died {
+                    entity: damage.target,
+                });
+                continue;
+            }
+            health.lives -= 1;
+            health.current = health.max;
+            life_lost_events.send(LifeLost {
+                entity: damage.target,
+            });
+        }
+
+        if health.invulnerability_time > 0.0 {
+            commands.entity(damage.target).insert(Invulnerable {
+                timer: Timer::from_seconds(health.invulnerability_time, TimerMode::Once),
+            });
+        }
+    }
+}
+
+pub fn tick_invulnerability(
+    mut commands: Commands,
+    mut invulnerable_query: Query<(Entity, &mut Invulnerable, &mut Visibility)>,
+    fixed_time: Res<FixedTime>,
+) {
+    for (entity, mut invulnerable, mut visibility) in invulnerable_query.iter_mut() {
+        invulnerable.timer.tick(fixed_time.period);
+        if invulnerable.timer.finished() {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+    }
+}
+
+fn blink_invulnerable(mut invulnerable_query: Query<(&Invulnerable, &mut Visibility)>) {
+    for (invulnerable, mut visibility) in invulnerable_query.iter_mut() {
+        let phase = (invulnerable.timer.elapsed_secs() / BLINK_INTERVAL) as u32;
+        *visibility = if phase % 2 == 1 {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
@@ -0,0 +1,133 @@
+use asefile::{AnimationDirection, AsepriteFile};
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    utils::HashMap,
+};
+
+use crate::animation::{AnimationClip, AnimationFrame, AnimationSet, LoopMode};
+
+/// Loads Aseprite files straight from the artist's source, so saving in Aseprite hot reloads the
+/// game with no PNG export step.
+///
+/// Every frame goes into one row of a `TextureAtlas`, in timeline order:
+/// - `file.ase` is the atlas of all visible layers flattened together.
+/// - `file.ase#LAYER` is the atlas of a single layer, for files holding one character per layer.
+/// - `file.ase#animations` is an `AnimationSet` with a clip per tag, using the frame durations
+///   set in Aseprite. Loop modes, transitions and frame events go in an `AnimationRules` file
+///   keyed by tag name.
+#[derive(Default)]
+pub struct AsepriteLoader;
+
+impl AssetLoader for AsepriteLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let ase = AsepriteFile::read(bytes)?;
+            let frame_size = UVec2::new(ase.width() as u32, ase.height() as u32);
+
+            let flattened = (0..ase.num_frames()).map(|frame| ase.frame(frame).image().into_raw());
+            let atlas = frame_strip_atlas(load_context, "sheet", frame_size, flattened);
+            load_context.set_default_asset(LoadedAsset::new(atlas));
+
+            for layer_id in 0..ase.num_layers() {
+                let layer = ase.layer(layer_id);
+                let cels = (0..ase.num_frames()).map(|frame| layer.frame(frame).image().into_raw());
+                let image_label = format!("{}/sheet", layer.name());
+                let atlas = frame_strip_atlas(load_context, &image_label, frame_size, cels);
+                load_context.set_labeled_asset(layer.name(), LoadedAsset::new(atlas));
+            }
+
+            load_context.set_labeled_asset("animations", LoadedAsset::new(tag_clips(&ase)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ase", "aseprite"]
+    }
+}
+
+/// Lays the RGBA `frames` out left to right in one image, stored under `image_label`, and grids it.
+fn frame_strip_atlas(
+    load_context: &mut LoadContext,
+    image_label: &str,
+    frame_size: UVec2,
+    frames: impl ExactSizeIterator<Item = Vec<u8>>,
+) -> TextureAtlas {
+    let frame_count = frames.len() as u32;
+    let row_bytes = frame_size.x as usize * 4;
+    let strip_row_bytes = row_bytes * frame_count as usize;
+    let mut data = vec![0; strip_row_bytes * frame_size.y as usize];
+
+    for (index, pixels) in frames.enumerate() {
+        for y in 0..frame_size.y as usize {
+            let source = y * row_bytes;
+            let target = y * strip_row_bytes + index * row_bytes;
+            data[target..target + row_bytes].copy_from_slice(&pixels[source..source + row_bytes]);
+        }
+    }
+
+    let image = Image::new(
+        Extent3d {
+            width: frame_size.x * frame_count,
+            height: frame_size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    let texture = load_context.set_labeled_asset(image_label, LoadedAsset::new(image));
+
+    TextureAtlas::from_grid(
+        texture,
+        frame_size.as_vec2(),
+        frame_count as usize,
+        1,
+        None,
+        None,
+    )
+}
+
+fn tag_clips(ase: &AsepriteFile) -> AnimationSet {
+    let mut clips = HashMap::default();
+    for tag_id in 0..ase.num_tags() {
+        let tag = ase.tag(tag_id);
+        let mut indices: Vec<u32> = (tag.from_frame()..=tag.to_frame()).collect();
+        let mode = match tag.animation_direction() {
+            AnimationDirection::Forward => LoopMode::Loop,
+            AnimationDirection::Reverse => {
+                indices.reverse();
+                LoopMode::Loop
+            }
+            AnimationDirection::PingPong => LoopMode::PingPong,
+        };
+
+        let frames = indices
+            .into_iter()
+            .map(|index| AnimationFrame {
+                index: index as usize,
+                duration: ase.frame(index).duration() as f32 / 1000.0,
+            })
+            .collect();
+        clips.insert(
+            tag.name().to_string(),
+            AnimationClip {
+                frames,
+                mode,
+                next: None,
+                events: Vec::new(),
+            },
+        );
+    }
+
+    AnimationSet {
+        clips,
+        transitions: Vec::new(),
+    }
+}
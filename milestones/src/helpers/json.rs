@@ -0,0 +1,56 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{Asset, AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    prelude::*,
+};
+use serde::de::DeserializeOwned;
+
+/// Registers `A` as an asset deserialized from JSON files ending in one of `extensions`.
+///
+/// Use a compound extension such as `movement.json` so each asset type gets its own loader.
+pub struct JsonAssetPlugin<A> {
+    extensions: Vec<&'static str>,
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<A> JsonAssetPlugin<A> {
+    pub fn new(extensions: &[&'static str]) -> Self {
+        JsonAssetPlugin {
+            extensions: extensions.to_vec(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A: Asset + DeserializeOwned> Plugin for JsonAssetPlugin<A> {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<A>().add_asset_loader(JsonAssetLoader::<A> {
+            extensions: self.extensions.clone(),
+            _marker: PhantomData,
+        });
+    }
+}
+
+pub struct JsonAssetLoader<A> {
+    extensions: Vec<&'static str>,
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<A: Asset + DeserializeOwned> AssetLoader for JsonAssetLoader<A> {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let asset: A = serde_json::from_slice(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(asset));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
@@ -1 +1,5 @@
+pub mod aseprite;
+pub mod json;
+// The old hand-rolled loader, kept for reference now that bevy_ecs_ldtk loads the levels
+#[allow(dead_code)]
 pub mod ldtk;
@@ -0,0 +1,529 @@
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::HashMap;
+use serde::Deserialize;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::helpers::json::JsonAssetPlugin;
+
+/// Key and button bindings loaded at startup and hot reloaded on save.
+const BINDINGS_PATH: &str = "config/default.bindings.json";
+
+/// How far down the stick has to be pushed to count as holding Down.
+const STICK_DOWN_THRESHOLD: f32 = 0.5;
+
+pub struct ActionInputPlugin;
+
+impl Plugin for ActionInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(JsonAssetPlugin::<InputBindings>::new(&["bindings.json"]))
+            .init_resource::<InputBindings>()
+            .init_resource::<Input<PlayerAction>>()
+            .init_resource::<AnalogMovement>()
+            .init_resource::<ActiveGamepad>()
+            .add_systems(Startup, load_input_bindings)
+            .add_systems(Update, apply_input_bindings)
+            .add_systems(
+                PreUpdate,
+                (track_active_gamepad, update_action_input)
+                    .chain()
+                    .after(InputSystem),
+            );
+    }
+}
+
+/// Everything the game can be asked to do from an input device.
+///
+/// Gameplay reads `Res<Input<PlayerAction>>` and never a physical key or button, so bindings can
+/// change freely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum PlayerAction {
+    MoveLeft,
+    MoveRight,
+    MoveDown,
+    Jump,
+    Run,
+    Attack,
+    Pause,
+    ToggleInspector,
+    ToggleNavigationDebug,
+    Exit,
+    MenuUp,
+    MenuDown,
+    MenuConfirm,
+}
+
+impl PlayerAction {
+    pub const ALL: [PlayerAction; 13] = [
+        PlayerAction::MoveLeft,
+        PlayerAction::MoveRight,
+        PlayerAction::MoveDown,
+        PlayerAction::Jump,
+        PlayerAction::Run,
+        PlayerAction::Attack,
+        PlayerAction::Pause,
+        PlayerAction::ToggleInspector,
+        PlayerAction::ToggleNavigationDebug,
+        PlayerAction::Exit,
+        PlayerAction::MenuUp,
+        PlayerAction::MenuDown,
+        PlayerAction::MenuConfirm,
+    ];
+
+    /// Menu actions are only read while paused, so they may share inputs with gameplay actions.
+    pub fn is_menu(self) -> bool {
+        matches!(
+            self,
+            PlayerAction::MenuUp | PlayerAction::MenuDown | PlayerAction::MenuConfirm
+        )
+    }
+}
+
+/// Which keys and gamepad buttons trigger each action. Any of an action's inputs counts as
+/// pressing it.
+#[derive(Resource, Deserialize, TypeUuid, TypePath, Clone, Debug)]
+#[uuid = "2ea5a468-887e-4721-be17-6d79699e91cc"]
+pub struct InputBindings {
+    pub keys: HashMap<PlayerAction, Vec<KeyCode>>,
+    #[serde(default)]
+    pub buttons: HashMap<PlayerAction, Vec<GamepadButtonType>>,
+    #[serde(default)]
+    pub stick: StickSettings,
+}
+
+/// Left stick tuning. Both values are fractions of full deflection.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct StickSettings {
+    /// Deflection below this is ignored; the rest of the range is rescaled to start from zero.
+    pub deadzone: f32,
+    /// Deflection at or past this runs instead of walking.
+    pub run_threshold: f32,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        StickSettings {
+            deadzone: 0.2,
+            run_threshold: 0.85,
+        }
+    }
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        let keys = [
+            (PlayerAction::MoveLeft, vec![KeyCode::A, KeyCode::Left]),
+            (PlayerAction::MoveRight, vec![KeyCode::D, KeyCode::Right]),
+            (PlayerAction::MoveDown, vec![KeyCode::S, KeyCode::Down]),
+            (PlayerAction::Jump, vec![KeyCode::Space]),
+            (
+                PlayerAction::Run,
+                vec![KeyCode::ShiftLeft, KeyCode::ShiftRight],
+            ),
+            (PlayerAction::Attack, vec![KeyCode::J]),
+            (PlayerAction::Pause, vec![KeyCode::P]),
+            (PlayerAction::ToggleInspector, vec![KeyCode::Grave]),
+            (PlayerAction::ToggleNavigationDebug, vec![KeyCode::F3]),
+            (PlayerAction::Exit, vec![KeyCode::Escape]),
+            (PlayerAction::MenuUp, vec![KeyCode::W, KeyCode::Up]),
+            (PlayerAction::MenuDown, vec![KeyCode::S, KeyCode::Down]),
+            (PlayerAction::MenuConfirm, vec![KeyCode::Return]),
+        ];
+        let buttons = [
+            (PlayerAction::MoveLeft, vec![GamepadButtonType::DPadLeft]),
+            (PlayerAction::MoveRight, vec![GamepadButtonType::DPadRight]),
+            (PlayerAction::MoveDown, vec![GamepadButtonType::DPadDown]),
+            (PlayerAction::Jump, vec![GamepadButtonType::South]),
+            (PlayerAction::Run, vec![GamepadButtonType::West]),
+            (PlayerAction::Attack, vec![GamepadButtonType::East]),
+            (PlayerAction::Pause, vec![GamepadButtonType::Start]),
+            (PlayerAction::MenuUp, vec![GamepadButtonType::DPadUp]),
+            (PlayerAction::MenuDown, vec![GamepadButtonType::DPadDown]),
+            (PlayerAction::MenuConfirm, vec![GamepadButtonType::South]),
+        ];
+
+        InputBindings {
+            keys: keys.into_iter().collect(),
+            buttons: buttons.into_iter().collect(),
+            stick: StickSettings::default(),
+        }
+    }
+}
+
+impl InputBindings {
+    /// Keys and buttons bound to more than one action of the same kind, with the actions they
+    /// trigger.
+    pub fn conflicts(&self) -> Vec<String> {
+        let key_conflicts = conflicting_inputs(&self.keys).into_iter();
+        let button_conflicts = conflicting_inputs(&self.buttons).into_iter();
+        key_conflicts
+            .map(|(key, actions)| format!("{key:?} is bound to several actions: {actions:?}"))
+            .chain(button_conflicts.map(|(button, actions)| {
+                format!("{button:?} is bound to several actions: {actions:?}")
+            }))
+            .collect()
+    }
+}
+
+fn conflicting_inputs<T: Copy + Eq + Hash + Debug>(
+    bindings: &HashMap<PlayerAction, Vec<T>>,
+) -> Vec<(T, Vec<PlayerAction>)> {
+    let mut actions_by_input: HashMap<(T, bool), Vec<PlayerAction>> = HashMap::default();
+    for (action, inputs) in bindings.iter() {
+        for input in inputs.iter() {
+            actions_by_input
+                .entry((*input, action.is_menu()))
+                .or_default()
+                .push(*action);
+        }
+    }
+    actions_by_input
+        .into_iter()
+        .filter(|(_, actions)| actions.len() > 1)
+        .map(|((input, _), actions)| (input, actions))
+        .collect()
+}
+
+/// Left stick position after the deadzone, for movement that cares about more than on/off.
+#[derive(Resource, Default, Debug)]
+pub struct AnalogMovement {
+    pub direction: Vec2,
+    pub is_running: bool,
+    pub is_holding_down: bool,
+}
+
+/// The gamepad whose buttons and stick are read. Follows connects and disconnects.
+#[derive(Resource, Default, Debug)]
+pub struct ActiveGamepad(pub Option<Gamepad>);
+
+/// The bindings file in use. Holding the handle keeps it loaded so edits keep being picked up.
+#[derive(Resource)]
+struct InputBindingsHandle(Handle<InputBindings>);
+
+fn load_input_bindings(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(InputBindingsHandle(asset_server.load(BINDINGS_PATH)));
+}
+
+fn apply_input_bindings(
+    mut binding_events: EventReader<AssetEvent<InputBindings>>,
+    binding_assets: Res<Assets<InputBindings>>,
+    bindings_handle: Res<InputBindingsHandle>,
+    mut bindings: ResMut<InputBindings>,
+) {
+    for event in binding_events.iter() {
+        let (AssetEvent::Created { handle } | AssetEvent::Modified { handle }) = event else {
+            continue;
+        };
+        if *handle != bindings_handle.0 {
+            continue;
+        }
+        let Some(loaded) = binding_assets.get(handle) else {
+            continue;
+        };
+
+        for conflict in loaded.conflicts() {
+            warn!("{conflict}");
+        }
+        *bindings = loaded.clone();
+    }
+}
+
+/// Picks up the first gamepad to connect, and falls back to any other one when it disconnects.
+pub fn track_active_gamepad(
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+    gamepads: Res<Gamepads>,
+    mut active_gamepad: ResMut<ActiveGamepad>,
+) {
+    for event in connection_events.iter() {
+        match &event.connection {
+            GamepadConnection::Connected(gamepad_info) => {
+                if active_gamepad.0.is_none() {
+                    info!("Using gamepad {}", gamepad_info.name);
+                    active_gamepad.0 = Some(event.gamepad);
+                }
+            }
+            GamepadConnection::Disconnected => {
+                if active_gamepad.0 == Some(event.gamepad) {
+                    active_gamepad.0 = gamepads.iter().find(|gamepad| *gamepad != event.gamepad);
+                }
+            }
+        }
+    }
+}
+
+/// Folds keyboard, gamepad buttons and the left stick into actions.
+///
+/// Only reads Bevy's input resources, so a headless app can drive it by sending `GamepadEvent`s.
+pub fn update_action_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    active_gamepad: Res<ActiveGamepad>,
+    bindings: Res<InputBindings>,
+    mut action_input: ResMut<Input<PlayerAction>>,
+    mut analog_movement: ResMut<AnalogMovement>,
+) {
+    action_input.clear();
+    for action in PlayerAction::ALL {
+        let key_pressed = bindings
+            .keys
+            .get(&action)
+            .is_some_and(|keys| keyboard_input.any_pressed(keys.iter().copied()));
+        let button_pressed = match (active_gamepad.0, bindings.buttons.get(&action)) {
+            (Some(gamepad), Some(buttons)) => buttons
+                .iter()
+                .any(|button| gamepad_buttons.pressed(GamepadButton::new(gamepad, *button))),
+            _ => false,
+        };
+
+        if key_pressed || button_pressed {
+            action_input.press(action);
+        } else {
+            action_input.release(action);
+        }
+    }
+
+    let stick = active_gamepad.0.map_or(Vec2::ZERO, |gamepad| {
+        let axis = |axis_type| {
+            gamepad_axes
+                .get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.0)
+        };
+        Vec2::new(
+            axis(GamepadAxisType::LeftStickX),
+            axis(GamepadAxisType::LeftStickY),
+        )
+    });
+    let direction = apply_deadzone(stick, bindings.stick.deadzone);
+
+    analog_movement.direction = direction;
+    analog_movement.is_running =
+        direction != Vec2::ZERO && direction.length() >= bindings.stick.run_threshold;
+    analog_movement.is_holding_down = direction.y <= -STICK_DOWN_THRESHOLD;
+}
+
+/// Zeroes a stick inside a radial deadzone and rescales the rest so movement starts from zero at
+/// its edge.
+fn apply_deadzone(stick: Vec2, deadzone: f32) -> Vec2 {
+    let length = stick.length();
+    if length <= deadzone {
+        return Vec2::ZERO;
+    }
+    let scaled_length = ((length - deadzone) / (1.0 - deadzone)).min(1.0);
+    stick / length * scaled_length
+}
+
+/// Run condition that flips between on and off each time `action` is pressed, starting at `default`.
+pub fn action_toggle_active(
+    default: bool,
+    action: PlayerAction,
+) -> impl FnMut(Res<Input<PlayerAction>>) -> bool + Clone {
+    let mut active = default;
+    move |action_input: Res<Input<PlayerAction>>| {
+        active ^= action_input.just_pressed(action);
+        active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::gamepad::{
+        GamepadAxisChangedEvent, GamepadButtonChangedEvent, GamepadEvent, GamepadInfo,
+    };
+    use bevy::input::InputPlugin;
+
+    use super::*;
+
+    fn headless_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            InputPlugin,
+            ActionInputPlugin,
+        ));
+        app.update();
+        app
+    }
+
+    fn connect(app: &mut App, id: usize) {
+        let info = GamepadInfo {
+            name: format!("Test gamepad {id}"),
+        };
+        app.world
+            .send_event(GamepadEvent::Connection(GamepadConnectionEvent::new(
+                Gamepad::new(id),
+                GamepadConnection::Connected(info),
+            )));
+    }
+
+    fn disconnect(app: &mut App, id: usize) {
+        app.world
+            .send_event(GamepadEvent::Connection(GamepadConnectionEvent::new(
+                Gamepad::new(id),
+                GamepadConnection::Disconnected,
+            )));
+    }
+
+    fn set_button(app: &mut App, id: usize, button: GamepadButtonType, value: f32) {
+        app.world
+            .send_event(GamepadEvent::Button(GamepadButtonChangedEvent::new(
+                Gamepad::new(id),
+                button,
+                value,
+            )));
+    }
+
+    fn set_stick(app: &mut App, id: usize, stick: Vec2) {
+        for (axis, value) in [
+            (GamepadAxisType::LeftStickX, stick.x),
+            (GamepadAxisType::LeftStickY, stick.y),
+        ] {
+            app.world
+                .send_event(GamepadEvent::Axis(GamepadAxisChangedEvent::new(
+                    Gamepad::new(id),
+                    axis,
+                    value,
+                )));
+        }
+    }
+
+    fn stick_settings(app: &App) -> StickSettings {
+        app.world.resource::<InputBindings>().stick
+    }
+
+    fn analog_movement(app: &App) -> &AnalogMovement {
+        app.world.resource::<AnalogMovement>()
+    }
+
+    fn action_input(app: &App) -> &Input<PlayerAction> {
+        app.world.resource::<Input<PlayerAction>>()
+    }
+
+    #[test]
+    fn gamepad_buttons_press_actions() {
+        let mut app = headless_app();
+        connect(&mut app, 0);
+        app.update();
+
+        set_button(&mut app, 0, GamepadButtonType::South, 1.0);
+        app.update();
+        assert!(action_input(&app).just_pressed(PlayerAction::Jump));
+
+        app.update();
+        assert!(action_input(&app).pressed(PlayerAction::Jump));
+        assert!(!action_input(&app).just_pressed(PlayerAction::Jump));
+
+        set_button(&mut app, 0, GamepadButtonType::South, 0.0);
+        app.update();
+        assert!(!action_input(&app).pressed(PlayerAction::Jump));
+        assert!(action_input(&app).just_released(PlayerAction::Jump));
+    }
+
+    #[test]
+    fn stick_inside_deadzone_is_ignored() {
+        let mut app = headless_app();
+        connect(&mut app, 0);
+        let settings = stick_settings(&app);
+
+        set_stick(&mut app, 0, Vec2::new(settings.deadzone * 0.75, 0.0));
+        app.update();
+        assert_eq!(analog_movement(&app).direction, Vec2::ZERO);
+        assert!(!analog_movement(&app).is_running);
+    }
+
+    #[test]
+    fn stick_is_rescaled_past_the_deadzone() {
+        let mut app = headless_app();
+        connect(&mut app, 0);
+        let settings = stick_settings(&app);
+
+        // Halfway between the deadzone and full deflection
+        let deflection = (settings.deadzone + 1.0) / 2.0;
+        set_stick(&mut app, 0, Vec2::new(-deflection, 0.0));
+        app.update();
+        let direction = analog_movement(&app).direction;
+        assert!((direction.x + 0.5).abs() < 1e-3, "{direction:?}");
+        assert_eq!(direction.y, 0.0);
+    }
+
+    #[test]
+    fn stick_runs_past_the_run_threshold() {
+        let mut app = headless_app();
+        connect(&mut app, 0);
+        let settings = stick_settings(&app);
+
+        // Just short of running once rescaled
+        let walk = settings.deadzone + (1.0 - settings.deadzone) * settings.run_threshold * 0.9;
+        set_stick(&mut app, 0, Vec2::new(walk, 0.0));
+        app.update();
+        assert!(!analog_movement(&app).is_running);
+
+        set_stick(&mut app, 0, Vec2::new(1.0, 0.0));
+        app.update();
+        assert!(analog_movement(&app).is_running);
+    }
+
+    #[test]
+    fn stick_pushed_down_holds_down() {
+        let mut app = headless_app();
+        connect(&mut app, 0);
+
+        set_stick(&mut app, 0, Vec2::new(0.0, -1.0));
+        app.update();
+        assert!(analog_movement(&app).is_holding_down);
+
+        set_stick(&mut app, 0, Vec2::ZERO);
+        app.update();
+        assert!(!analog_movement(&app).is_holding_down);
+    }
+
+    #[test]
+    fn unplugging_the_active_gamepad_falls_back_to_another() {
+        let mut app = headless_app();
+        connect(&mut app, 0);
+        connect(&mut app, 1);
+        app.update();
+        assert_eq!(
+            app.world.resource::<ActiveGamepad>().0,
+            Some(Gamepad::new(0))
+        );
+
+        set_button(&mut app, 0, GamepadButtonType::South, 1.0);
+        set_stick(&mut app, 0, Vec2::new(1.0, 0.0));
+        app.update();
+        assert!(action_input(&app).pressed(PlayerAction::Jump));
+        assert_ne!(analog_movement(&app).direction, Vec2::ZERO);
+
+        disconnect(&mut app, 0);
+        app.update();
+        assert_eq!(
+            app.world.resource::<ActiveGamepad>().0,
+            Some(Gamepad::new(1))
+        );
+        assert!(!action_input(&app).pressed(PlayerAction::Jump));
+        assert_eq!(analog_movement(&app).direction, Vec2::ZERO);
+
+        disconnect(&mut app, 1);
+        app.update();
+        assert_eq!(app.world.resource::<ActiveGamepad>().0, None);
+    }
+
+    #[test]
+    fn reconnecting_after_unplugging_resumes_input() {
+        let mut app = headless_app();
+        connect(&mut app, 0);
+        app.update();
+        disconnect(&mut app, 0);
+        app.update();
+        assert_eq!(app.world.resource::<ActiveGamepad>().0, None);
+
+        connect(&mut app, 0);
+        set_button(&mut app, 0, GamepadButtonType::East, 1.0);
+        app.update();
+        assert!(action_input(&app).pressed(PlayerAction::Attack));
+    }
+}
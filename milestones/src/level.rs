@@ -0,0 +1,323 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy_ecs_ldtk::ldtk::FieldValue;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::archetype::{spawn_enemy, EnemyArchetypes, EnemyAssets};
+use crate::behaviour::{BehaviourConfig, Patrol};
+use crate::collectible::{spawn_collectible, Collectible, CollectibleAssets, CollectibleRegistry};
+use crate::collider::{Collider, OverlapStarted};
+use crate::collision::TILE_SIZE;
+use crate::health::{apply_damage, LifeLost};
+use crate::offscreen::{DespawnWhenOffscreen, Pool};
+use crate::player::Velocity;
+use crate::timestep::{InterpolatedTransform, SimulationSet};
+use crate::GameState;
+use crate::Player;
+
+const CHECKPOINT_COLOR: Color = Color::rgb(0.4, 0.4, 0.5);
+const ACTIVE_CHECKPOINT_COLOR: Color = Color::rgb(0.2, 0.9, 0.95);
+
+/// Places the player, enemies, collectibles and checkpoints where the level's `Entities` layer
+/// puts them.
+///
+/// LDtk spawns its entities as children of the level, so each one is only a marker. Once the
+/// level's transforms have propagated, a top level gameplay entity is spawned at the marker's world
+/// position and the marker is left in place.
+pub struct LevelEntitiesPlugin;
+
+impl Plugin for LevelEntitiesPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<PlayerStartBundle>("PlayerStart")
+            .register_ldtk_entity::<EnemyPlacementBundle>("Enemy")
+            .register_ldtk_entity::<CollectiblePlacementBundle>("Collectible")
+            .register_ldtk_entity::<CheckpointPlacementBundle>("Checkpoint")
+            .init_resource::<RespawnPoint>()
+            .add_systems(
+                PostUpdate,
+                (place_enemies, place_collectibles, place_checkpoints)
+                    .after(TransformSystem::TransformPropagate)
+                    .run_if(in_state(GameState::Running)),
+            )
+            .add_systems(
+                Update,
+                remember_player_start.run_if(in_state(GameState::Running)),
+            )
+            .add_systems(
+                FixedUpdate,
+                reach_checkpoint
+                    .in_set(SimulationSet::Collision)
+                    .run_if(in_state(GameState::Running)),
+            )
+            .add_systems(
+                FixedUpdate,
+                respawn_at_checkpoint
+                    .after(apply_damage)
+                    .in_set(SimulationSet::Damage)
+                    .run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
+/// Where the player is spawned. Without one in the level they start in the middle of the window.
+#[derive(Component, Default)]
+pub struct PlayerStart;
+
+#[derive(Bundle, LdtkEntity, Default)]
+struct PlayerStartBundle {
+    player_start: PlayerStart,
+}
+
+/// An enemy placed in the level, with its LDtk fields.
+#[derive(Component, Default, Debug)]
+pub struct EnemyPlacement {
+    /// Name in `default.enemies.json`.
+    pub archetype: String,
+    /// Overrides the archetype's speed.
+    pub speed: Option<f32>,
+    /// Patrol route as offsets from the placement. Replaces any patrol the archetype has.
+    pub patrol: Vec<Vec2>,
+}
+
+impl From<&EntityInstance> for EnemyPlacement {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let mut placement = EnemyPlacement::default();
+        for field in entity_instance.field_instances.iter() {
+            match (field.identifier.as_str(), &field.value) {
+                ("Archetype", FieldValue::String(Some(archetype))) => {
+                    placement.archetype = archetype.clone()
+                }
+                ("Speed", FieldValue::Float(speed)) => placement.speed = *speed,
+                ("Patrol", FieldValue::Points(points)) => {
+                    // LDtk points are grid cells counted down from the top of the level
+                    placement.patrol = std::iter::once(Vec2::ZERO)
+                        .chain(points.iter().flatten().map(|point| {
+                            let cells = *point - entity_instance.grid;
+                            Vec2::new(cells.x as f32, -cells.y as f32) * TILE_SIZE
+                        }))
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+        placement
+    }
+}
+
+#[derive(Bundle, LdtkEntity, Default)]
+struct EnemyPlacementBundle {
+    #[from_entity_instance]
+    placement: EnemyPlacement,
+}
+
+/// A collectible placed in the level, with its LDtk fields.
+#[derive(Component, Default, Debug)]
+pub struct CollectiblePlacement {
+    /// Name in `default.collectibles.json`.
+    pub kind: String,
+}
+
+impl From<&EntityInstance> for CollectiblePlacement {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let mut placement = CollectiblePlacement::default();
+        for field in entity_instance.field_instances.iter() {
+            if let ("Kind", FieldValue::String(Some(kind))) =
+                (field.identifier.as_str(), &field.value)
+            {
+                placement.kind = kind.clone();
+            }
+        }
+        placement
+    }
+}
+
+#[derive(Bundle, LdtkEntity, Default)]
+struct CollectiblePlacementBundle {
+    #[from_entity_instance]
+    placement: CollectiblePlacement,
+}
+
+#[derive(Component, Default)]
+pub struct CheckpointPlacement;
+
+#[derive(Bundle, LdtkEntity, Default)]
+struct CheckpointPlacementBundle {
+    checkpoint_placement: CheckpointPlacement,
+    #[from_entity_instance]
+    entity_instance: EntityInstance,
+}
+
+/// Marks a placement whose gameplay entity has been spawned.
+#[derive(Component)]
+struct Placed;
+
+/// Touching one makes it the place the player comes back to after losing a life.
+#[derive(Component)]
+pub struct Checkpoint;
+
+/// Where the player reappears after losing a life: the last checkpoint reached, or where they
+/// started.
+#[derive(Resource, Default)]
+pub struct RespawnPoint(pub Option<Vec2>);
+
+fn place_enemies(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    enemy_assets: Res<EnemyAssets>,
+    archetypes: Res<Assets<EnemyArchetypes>>,
+    placement_query: Query<(Entity, &GlobalTransform, &EnemyPlacement), Without<Placed>>,
+) {
+    // Left unplaced until the archetypes have loaded
+    let Some(archetypes) = archetypes.get(&enemy_assets.archetypes) else {
+        return;
+    };
+
+    for (entity, transform, placement) in placement_query.iter() {
+        commands.entity(entity).insert(Placed);
+
+        let Some(archetype) = archetypes.get(&placement.archetype) else {
+            warn!(
+                "The level places an unknown enemy archetype {:?}",
+                placement.archetype
+            );
+            continue;
+        };
+        let mut archetype = archetype.clone();
+        if let Some(speed) = placement.speed {
+            archetype.speed = speed;
+        }
+        if placement.patrol.len() > 1 {
+            archetype
+                .behaviours
+                .retain(|behaviour| !matches!(behaviour, BehaviourConfig::Patrol(_)));
+            let patrol = Patrol::new(placement.patrol.clone());
+            archetype.behaviours.push(BehaviourConfig::Patrol(patrol));
+        }
+
+        let enemy = spawn_enemy(
+            &mut commands,
+            &asset_server,
+            &archetype,
+            transform.translation().truncate(),
+        );
+        // Waits where it was placed until the player gets there
+        commands
+            .entity(enemy)
+            .insert(DespawnWhenOffscreen::left_behind());
+    }
+}
+
+fn place_collectibles(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    collectible_assets: Res<CollectibleAssets>,
+    registries: Res<Assets<CollectibleRegistry>>,
+    mut collectible_pool: ResMut<Pool<Collectible>>,
+    placement_query: Query<(Entity, &GlobalTransform, &CollectiblePlacement), Without<Placed>>,
+) {
+    // Left unplaced until the registry has loaded
+    let Some(registry) = registries.get(&collectible_assets.registry) else {
+        return;
+    };
+
+    for (entity, transform, placement) in placement_query.iter() {
+        commands.entity(entity).insert(Placed);
+        let Some(collectible) = spawn_collectible(
+            &mut commands,
+            &asset_server,
+            &mut collectible_pool,
+            registry,
+            &placement.kind,
+            transform.translation().truncate(),
+        ) else {
+            continue;
+        };
+        commands
+            .entity(collectible)
+            .insert(DespawnWhenOffscreen::left_behind());
+    }
+}
+
+fn place_checkpoints(
+    mut commands: Commands,
+    placement_query: Query<
+        (Entity, &GlobalTransform, &EntityInstance),
+        (With<CheckpointPlacement>, Without<Placed>),
+    >,
+) {
+    for (entity, transform, entity_instance) in placement_query.iter() {
+        commands.entity(entity).insert(Placed);
+
+        let size = Vec2::new(entity_instance.width as f32, entity_instance.height as f32);
+        let translation = transform.translation().truncate().extend(0.0);
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: CHECKPOINT_COLOR,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            Checkpoint,
+            Collider::Aabb {
+                half_extents: size / 2.0,
+            },
+        ));
+    }
+}
+
+fn remember_player_start(
+    mut respawn_point: ResMut<RespawnPoint>,
+    player_query: Query<&Transform, Added<Player>>,
+) {
+    if let Ok(transform) = player_query.get_single() {
+        respawn_point.0 = Some(transform.translation.truncate());
+    }
+}
+
+fn reach_checkpoint(
+    mut overlap_events: EventReader<OverlapStarted>,
+    mut respawn_point: ResMut<RespawnPoint>,
+    player_query: Query<(), With<Player>>,
+    checkpoint_query: Query<(), With<Checkpoint>>,
+    mut sprite_query: Query<(Entity, &Transform, &mut Sprite), With<Checkpoint>>,
+) {
+    for OverlapStarted(pair) in overlap_events.iter() {
+        let Some((_, reached)) = pair.matching(&player_query, &checkpoint_query) else {
+            continue;
+        };
+
+        for (entity, transform, mut sprite) in sprite_query.iter_mut() {
+            if entity == reached {
+                respawn_point.0 = Some(transform.translation.truncate());
+                sprite.color = ACTIVE_CHECKPOINT_COLOR;
+            } else {
+                sprite.color = CHECKPOINT_COLOR;
+            }
+        }
+    }
+}
+
+fn respawn_at_checkpoint(
+    mut life_lost_events: EventReader<LifeLost>,
+    respawn_point: Res<RespawnPoint>,
+    mut player_query: Query<
+        (&mut Transform, &mut InterpolatedTransform, &mut Velocity),
+        With<Player>,
+    >,
+) {
+    for life_lost in life_lost_events.iter() {
+        let (Some(position), Ok((mut transform, mut interpolated, mut velocity))) =
+            (respawn_point.0, player_query.get_mut(life_lost.entity))
+        else {
+            continue;
+        };
+
+        transform.translation = position.extend(transform.translation.z);
+        // Also move the last snapshot so the player doesn't visibly slide back across the level
+        interpolated.current = transform.translation;
+        velocity.value = Vec3::ZERO;
+    }
+}
@@ -1,15 +1,13 @@
+// Bevy systems take everything they use as arguments, and their queries spell out long types
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+use bevy::asset::ChangeWatcher;
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
-use bevy::input::common_conditions::input_toggle_active;
-use bevy::input::mouse::MouseWheel;
-use bevy::window::PrimaryWindow;
 use bevy_ecs_ldtk::prelude::*;
-use bevy_inspector_egui::prelude::ReflectInspectorOptions;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
-use bevy_inspector_egui::InspectorOptions;
-
-use bevy::diagnostic::DiagnosticsStore;
 
 use bevy::prelude::*;
+use bevy::utils::Duration;
 
 // bevy_asset_loader imports
 use bevy_asset_loader::prelude::*;
@@ -32,16 +30,60 @@ mod tilemap;
 
 mod helpers;
 
+mod collision;
+
+mod collider;
+
+mod timestep;
+
+mod health;
+
+mod input;
+
+mod character;
+
+mod animation;
+
+mod attack;
+
+mod behaviour;
+
+mod archetype;
+
+mod level;
+
+mod waves;
+
+mod navigation;
+
+mod projectile;
+
+mod offscreen;
+
+mod collectible;
+
+use crate::animation::{AnimationPlugin, AnimationSet};
+use crate::attack::AttackPlugin;
+use crate::behaviour::EnemyBehaviourPlugin;
 use crate::cam::*;
+use crate::character::{Character, CharacterSelectPlugin};
+use crate::collectible::CollectiblePlugin;
+use crate::collider::ColliderPlugin;
+use crate::collision::TerrainPlugin;
 use crate::enemy::*;
 use crate::environment::*;
+use crate::health::HealthPlugin;
+use crate::input::{action_toggle_active, ActionInputPlugin, PlayerAction};
+use crate::level::LevelEntitiesPlugin;
+use crate::navigation::NavigationPlugin;
+use crate::offscreen::OffscreenPlugin;
 use crate::player::*;
-use crate::prelude::*;
+use crate::projectile::ProjectilePlugin;
 use crate::stars::*;
 use crate::tilemap::TilemapPlugin;
+use crate::timestep::{FixedTimestepPlugin, SimulationSet};
 use crate::ui::*;
-
-use seldom_pixel::PxPlugin;
+use crate::waves::SpawnDirectorPlugin;
 
 #[derive(Event)]
 pub struct GameOver {
@@ -52,25 +94,50 @@ struct PhysicsPlugin;
 
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Gravity>()
-            .add_systems(Update, physics_system.run_if(in_state(GameState::Running)));
+        app.init_resource::<Gravity>().add_systems(
+            FixedUpdate,
+            physics_system
+                .in_set(SimulationSet::Physics)
+                .run_if(in_state(GameState::Running)),
+        );
     }
 }
 
 #[derive(Default, Debug, Clone, Eq, PartialEq, Hash, States)]
 enum GameState {
     #[default]
-    Running,
     Loading,
+    CharacterSelect,
+    Running,
     Paused,
 }
 
 // Example asset collection for an image asset
 #[derive(AssetCollection, Resource)]
 pub struct PlayerAnimation {
-    #[asset(texture_atlas(tile_size_x = 24.0, tile_size_y = 24.0, columns = 24, rows = 1))]
-    #[asset(path = "sprites/sheets/DinoSprites - doux.png")]
-    player: Handle<TextureAtlas>,
+    // One layer per dino in the Aseprite source, so art edits hot reload without exporting
+    #[asset(path = "sprites/aseprite/DinoSprites.ase#DOUX")]
+    doux: Handle<TextureAtlas>,
+    #[asset(path = "sprites/aseprite/DinoSprites.ase#MORT")]
+    mort: Handle<TextureAtlas>,
+    #[asset(path = "sprites/aseprite/DinoSprites.ase#TARD")]
+    tard: Handle<TextureAtlas>,
+    #[asset(path = "sprites/aseprite/DinoSprites.ase#VITA")]
+    vita: Handle<TextureAtlas>,
+    /// Clips from the file's tags, shared by every sheet.
+    #[asset(path = "sprites/aseprite/DinoSprites.ase#animations")]
+    pub animations: Handle<AnimationSet>,
+}
+
+impl PlayerAnimation {
+    pub fn sheet(&self, character: Character) -> Handle<TextureAtlas> {
+        match character {
+            Character::Doux => self.doux.clone(),
+            Character::Mort => self.mort.clone(),
+            Character::Tard => self.tard.clone(),
+            Character::Vita => self.vita.clone(),
+        }
+    }
 }
 
 /// System set to allow ordering of `PanCamPlugin`
@@ -80,13 +147,22 @@ pub struct GameSystemSet;
 fn main() {
     App::new()
         .add_event::<GameOver>()
-        .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
+        .add_plugins(
+            DefaultPlugins
+                .set(ImagePlugin::default_nearest())
+                .set(AssetPlugin {
+                    // Reload tuning files and art as they are saved
+                    watch_for_changes: ChangeWatcher::with_delay(Duration::from_millis(200)),
+                    ..default()
+                }),
+        )
         .add_state::<GameState>()
         .add_plugins(LogDiagnosticsPlugin::default())
-        .add_plugins(FrameTimeDiagnosticsPlugin::default())
+        .add_plugins(FrameTimeDiagnosticsPlugin)
         .add_plugins(LdtkPlugin)
         .insert_resource(LevelSelection::Index(0))
         .add_plugins((
+            FixedTimestepPlugin,
             CameraPlugin,
             PlayerPlugin,
             PhysicsPlugin,
@@ -94,16 +170,32 @@ fn main() {
             EnemyPlugin,
             StarPlugin,
             TilemapPlugin,
+            TerrainPlugin,
+            ColliderPlugin,
+            HealthPlugin,
+            ActionInputPlugin,
+            CharacterSelectPlugin,
+            AnimationPlugin,
+        ))
+        .add_plugins((
+            AttackPlugin,
+            EnemyBehaviourPlugin,
+            LevelEntitiesPlugin,
+            SpawnDirectorPlugin,
+            NavigationPlugin,
+            ProjectilePlugin,
+            OffscreenPlugin,
+            CollectiblePlugin,
         ))
         .add_plugins(
-            WorldInspectorPlugin::default().run_if(input_toggle_active(false, KeyCode::Escape)),
+            WorldInspectorPlugin::default()
+                .run_if(action_toggle_active(false, PlayerAction::ToggleInspector)),
         )
         //.add_systems(Startup, spawn_platforms)
-        // Add a loading state for assets
+        // The select screen shows the dino sheets, so they have to be in before it opens
         .add_loading_state(
-            LoadingState::new(GameState::Loading).continue_to_state(GameState::Paused),
+            LoadingState::new(GameState::Loading).continue_to_state(GameState::CharacterSelect),
         )
-        // Initialize your asset collection
-        .init_collection::<PlayerAnimation>()
+        .add_collection_to_loading_state::<_, PlayerAnimation>(GameState::Loading)
         .run();
 }
@@ -0,0 +1,513 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use bevy::utils::HashMap;
+use bevy_ecs_ldtk::prelude::*;
+use futures_lite::future;
+
+use crate::collision::{
+    rebuild_terrain_colliders, KinematicBody, TerrainColliders, TerrainKind, TILE_SIZE,
+};
+use crate::input::{action_toggle_active, PlayerAction};
+use crate::GameState;
+
+/// Highest ledge an enemy can jump up to, in cells.
+const JUMP_HEIGHT: i32 = 4;
+/// Furthest an enemy can jump sideways, in cells.
+const JUMP_DISTANCE: i32 = 4;
+/// Furthest an enemy will drop off a ledge or through a one-way platform, in cells.
+const MAX_DROP: i32 = 12;
+/// Extra cost of a jump over walking the same distance, so paths only jump when it saves time.
+const JUMP_COST: f32 = 2.0;
+/// How far from a walkable cell a path can start or end, in cells.
+const SNAP_RADIUS: i32 = 6;
+/// Cells explored before a search gives up, so unreachable goals don't stall a worker.
+const MAX_EXPANSIONS: usize = 8192;
+/// How close an agent has to get to a waypoint to move on to the next one.
+const WAYPOINT_REACHED: f32 = TILE_SIZE / 2.0;
+
+const WALK_COLOR: Color = Color::rgb(0.3, 0.9, 0.3);
+const JUMP_COLOR: Color = Color::rgb(0.95, 0.8, 0.2);
+const DROP_COLOR: Color = Color::rgb(0.3, 0.6, 0.95);
+
+/// Finds paths over the level for enemies with a [`NavAgent`].
+///
+/// The terrain is turned into a graph of the cells a body can stand in, linked by walking, jumping
+/// and dropping. Bodies of different sizes fit through different gaps, so there is one graph per
+/// size in cells. Searches run on the async compute pool against a snapshot of that graph; an agent
+/// keeps following its old path until the new one arrives. Paths are thrown away whenever the level
+/// changes. Toggle the path overlay with F3.
+pub struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Navigation>()
+            .add_systems(
+                Update,
+                (
+                    rebuild_nav_grid,
+                    invalidate_paths,
+                    request_paths,
+                    collect_paths,
+                )
+                    .chain()
+                    .after(rebuild_terrain_colliders)
+                    .run_if(in_state(GameState::Running)),
+            )
+            .add_systems(
+                Update,
+                draw_paths.run_if(action_toggle_active(
+                    false,
+                    PlayerAction::ToggleNavigationDebug,
+                )),
+            );
+    }
+}
+
+/// How a path moves from one cell to the next.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LinkKind {
+    Walk,
+    Jump,
+    /// Falling off a ledge or through a one-way platform.
+    Drop,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct NavLink {
+    pub to: IVec2,
+    pub kind: LinkKind,
+    pub cost: f32,
+}
+
+/// Every cell a body of one size can stand in, with the links leading out of it. Cells are the
+/// [`TerrainColliders`] grid, and a body standing in a cell has its feet there.
+#[derive(Default, Debug)]
+pub struct NavGrid {
+    links: HashMap<IVec2, Vec<NavLink>>,
+}
+
+impl NavGrid {
+    /// Builds the graph for a body `clearance` cells wide and tall, see [`clearance`].
+    pub fn build(terrain: &TerrainColliders, clearance: IVec2) -> NavGrid {
+        let solid = |cell: IVec2| {
+            terrain
+                .tile(cell)
+                .is_some_and(|tile| tile.kind == TerrainKind::Solid)
+        };
+        // Nothing solid where the rest of the body would be with its feet in `cell`. The row the
+        // feet are in is left out so wide bodies can still stand on slopes and steps.
+        let half_width = clearance.x / 2;
+        let fits = |cell: IVec2| {
+            (1..clearance.y)
+                .all(|dy| (-half_width..=half_width).all(|dx| !solid(cell + IVec2::new(dx, dy))))
+        };
+        let clear = |cell: IVec2| !solid(cell) && fits(cell);
+
+        // Open cells on top of a floor, and the slopes themselves
+        let mut links: HashMap<IVec2, Vec<NavLink>> = HashMap::default();
+        for (cell, tile) in terrain.tiles() {
+            let standing = match tile.kind {
+                TerrainKind::Slope { .. } => cell,
+                TerrainKind::Solid | TerrainKind::OneWay => cell + IVec2::Y,
+            };
+            if (standing == cell || terrain.tile(standing).is_none()) && fits(standing) {
+                links.insert(standing, Vec::new());
+            }
+        }
+
+        let nodes: Vec<IVec2> = links.keys().copied().collect();
+        for &from in nodes.iter() {
+            let mut out = Vec::new();
+
+            for dx in [-1, 1] {
+                // Walking, including a cell up or down for slopes and steps
+                for dy in -1..=1 {
+                    let to = from + IVec2::new(dx, dy);
+                    let clear = match dy {
+                        1 => !solid(from + IVec2::Y),
+                        -1 => !solid(from + IVec2::new(dx, 0)),
+                        _ => true,
+                    };
+                    if clear && links.contains_key(&to) {
+                        out.push(link(from, to, LinkKind::Walk, 0.0));
+                    }
+                }
+
+                // Stepping off a ledge and falling until something is underfoot
+                let side = from + IVec2::new(dx, 0);
+                if terrain.tile(side).is_none()
+                    && !links.contains_key(&side)
+                    && !links.contains_key(&(side - IVec2::Y))
+                {
+                    if let Some(to) = landing(side, &links, clear) {
+                        out.push(link(from, to, LinkKind::Drop, 0.0));
+                    }
+                }
+            }
+
+            // Dropping through a one-way platform
+            let below = from - IVec2::Y;
+            if terrain
+                .tile(below)
+                .is_some_and(|tile| tile.kind == TerrainKind::OneWay)
+            {
+                if let Some(to) = landing(below - IVec2::Y, &links, clear) {
+                    out.push(link(from, to, LinkKind::Drop, 0.0));
+                }
+            }
+
+            // Jumping up to ledges and across gaps, over a box shaped arc the body must fit along
+            for dy in 0..=JUMP_HEIGHT {
+                for dx in -JUMP_DISTANCE..=JUMP_DISTANCE {
+                    let to = from + IVec2::new(dx, dy);
+                    let walkable = dx.abs() == 1 && dy <= 1;
+                    if dx == 0 || walkable || !links.contains_key(&to) {
+                        continue;
+                    }
+                    // Level ground can just be walked along
+                    let gap = (1..dx.abs()).any(|step| {
+                        !links.contains_key(&(from + IVec2::new(step * dx.signum(), 0)))
+                    });
+                    if dy == 0 && !gap {
+                        continue;
+                    }
+                    let peak = from.y.max(to.y) + 1;
+                    let rising = (from.y + 1..=peak).all(|y| clear(IVec2::new(from.x, y)));
+                    let across =
+                        (from.x.min(to.x)..=from.x.max(to.x)).all(|x| clear(IVec2::new(x, peak)));
+                    let falling = (to.y + 1..=peak).all(|y| clear(IVec2::new(to.x, y)));
+                    if rising && across && falling {
+                        out.push(link(from, to, LinkKind::Jump, JUMP_COST));
+                    }
+                }
+            }
+
+            links.insert(from, out);
+        }
+
+        NavGrid { links }
+    }
+
+    pub fn is_walkable(&self, cell: IVec2) -> bool {
+        self.links.contains_key(&cell)
+    }
+
+    pub fn links(&self, cell: IVec2) -> &[NavLink] {
+        self.links.get(&cell).map_or(&[], Vec::as_slice)
+    }
+
+    /// The walkable cell closest to `position`, within [`SNAP_RADIUS`].
+    pub fn nearest(&self, position: Vec2) -> Option<IVec2> {
+        let center = TerrainColliders::cell(position);
+        (-SNAP_RADIUS..=SNAP_RADIUS)
+            .flat_map(|y| (-SNAP_RADIUS..=SNAP_RADIUS).map(move |x| center + IVec2::new(x, y)))
+            .filter(|cell| self.is_walkable(*cell))
+            .min_by(|a, b| {
+                let a = cell_center(*a).distance_squared(position);
+                let b = cell_center(*b).distance_squared(position);
+                a.total_cmp(&b)
+            })
+    }
+
+    /// Cheapest route between the walkable cells nearest `from` and `to`, found with A*.
+    pub fn find_path(&self, from: Vec2, to: Vec2) -> Option<NavPath> {
+        let start = self.nearest(from)?;
+        let goal = self.nearest(to)?;
+
+        let heuristic = |cell: IVec2| (goal - cell).as_vec2().length();
+        let mut open = BinaryHeap::new();
+        let mut came_from: HashMap<IVec2, (IVec2, LinkKind)> = HashMap::default();
+        let mut best: HashMap<IVec2, f32> = HashMap::default();
+        open.push(OpenCell {
+            estimate: heuristic(start),
+            cell: start,
+        });
+        best.insert(start, 0.0);
+
+        let mut expansions = 0;
+        while let Some(OpenCell { cell, estimate }) = open.pop() {
+            if cell == goal {
+                return Some(NavPath::from_search(start, goal, &came_from));
+            }
+            let cost = best[&cell];
+            // A cheaper route to this cell was queued after this entry
+            if estimate > cost + heuristic(cell) {
+                continue;
+            }
+            expansions += 1;
+            if expansions > MAX_EXPANSIONS {
+                return None;
+            }
+
+            for link in self.links(cell) {
+                let cost = cost + link.cost;
+                if best.get(&link.to).is_some_and(|known| *known <= cost) {
+                    continue;
+                }
+                best.insert(link.to, cost);
+                came_from.insert(link.to, (cell, link.kind));
+                open.push(OpenCell {
+                    estimate: cost + heuristic(link.to),
+                    cell: link.to,
+                });
+            }
+        }
+        None
+    }
+}
+
+fn link(from: IVec2, to: IVec2, kind: LinkKind, penalty: f32) -> NavLink {
+    NavLink {
+        to,
+        kind,
+        cost: (to - from).as_vec2().length() + penalty,
+    }
+}
+
+/// First walkable cell at or below `cell`, unless the body would hit something solid on the way.
+fn landing(
+    cell: IVec2,
+    links: &HashMap<IVec2, Vec<NavLink>>,
+    clear: impl Fn(IVec2) -> bool,
+) -> Option<IVec2> {
+    (0..MAX_DROP)
+        .map(|depth| cell - IVec2::new(0, depth))
+        .take_while(|cell| clear(*cell))
+        .find(|cell| links.contains_key(cell))
+}
+
+fn cell_center(cell: IVec2) -> Vec2 {
+    (cell.as_vec2() + 0.5) * TILE_SIZE
+}
+
+/// A cell waiting to be explored, ordered so the heap pops the lowest estimate first.
+#[derive(PartialEq)]
+struct OpenCell {
+    estimate: f32,
+    cell: IVec2,
+}
+
+impl Eq for OpenCell {}
+
+impl Ord for OpenCell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for OpenCell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Waypoint {
+    /// Centre of the cell, in world space.
+    pub position: Vec2,
+    /// How to get here from the previous waypoint.
+    pub kind: LinkKind,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct NavPath {
+    pub waypoints: Vec<Waypoint>,
+}
+
+impl NavPath {
+    fn from_search(
+        start: IVec2,
+        goal: IVec2,
+        came_from: &HashMap<IVec2, (IVec2, LinkKind)>,
+    ) -> NavPath {
+        let mut waypoints = Vec::new();
+        let mut cell = goal;
+        while cell != start {
+            let (previous, kind) = came_from[&cell];
+            waypoints.push(Waypoint {
+                position: cell_center(cell),
+                kind,
+            });
+            cell = previous;
+        }
+        waypoints.push(Waypoint {
+            position: cell_center(start),
+            kind: LinkKind::Walk,
+        });
+        waypoints.reverse();
+        NavPath { waypoints }
+    }
+}
+
+/// The navigation graphs of the current level, one per body size and built the first time a body
+/// of that size asks. Searches share them with [`Arc`] so a rebuild never waits on them.
+#[derive(Resource, Default)]
+pub struct Navigation {
+    grids: HashMap<IVec2, Arc<NavGrid>>,
+    /// Bumped on every rebuild, so paths searched on an old graph can be told apart.
+    generation: u32,
+}
+
+impl Navigation {
+    /// The graph for bodies `clearance` cells wide and tall.
+    pub fn grid(&mut self, terrain: &TerrainColliders, clearance: IVec2) -> Arc<NavGrid> {
+        self.grids
+            .entry(clearance)
+            .or_insert_with(|| Arc::new(NavGrid::build(terrain, clearance)))
+            .clone()
+    }
+}
+
+/// Size of `body` in whole cells, at least one each way.
+pub fn clearance(body: &KinematicBody) -> IVec2 {
+    (body.size / TILE_SIZE).ceil().as_ivec2().max(IVec2::ONE)
+}
+
+/// Lets an enemy's behaviours route around the terrain. Behaviours say where they want to go with
+/// [`NavAgent::seek`] and steer towards [`NavAgent::waypoint`]; the search happens in the
+/// background. Paths are walked, jumped and fallen along, so agents need a [`KinematicBody`].
+#[derive(Component, Default)]
+pub struct NavAgent {
+    goal: Option<Vec2>,
+    path: Option<NavPath>,
+    next: usize,
+    /// Goal cell and graph generation of the last search.
+    searched: Option<(IVec2, u32)>,
+    task: Option<Task<Option<NavPath>>>,
+}
+
+impl NavAgent {
+    pub fn seek(&mut self, goal: Vec2) {
+        self.goal = Some(goal);
+    }
+
+    /// Drops the goal and any path to it.
+    pub fn stop(&mut self) {
+        self.goal = None;
+        self.invalidate();
+    }
+
+    /// Where to head next from `position`, or `None` without a path or once at its end.
+    pub fn waypoint(&mut self, position: Vec2) -> Option<Vec2> {
+        let waypoints = &self.path.as_ref()?.waypoints;
+        while self.next < waypoints.len()
+            && waypoints[self.next].position.distance(position) < WAYPOINT_REACHED
+        {
+            self.next += 1;
+        }
+        waypoints.get(self.next).map(|waypoint| waypoint.position)
+    }
+
+    /// Waypoints not reached yet.
+    pub fn remaining(&self) -> &[Waypoint] {
+        self.path.as_ref().map_or(&[], |path| {
+            &path.waypoints[self.next.min(path.waypoints.len())..]
+        })
+    }
+
+    fn invalidate(&mut self) {
+        self.path = None;
+        self.next = 0;
+        self.searched = None;
+        // Dropping the task cancels it
+        self.task = None;
+    }
+}
+
+/// Centre of the cell at the bottom of `body`, which is what paths start from and lead through.
+pub fn feet(position: Vec2, body: &KinematicBody) -> Vec2 {
+    position - Vec2::Y * (body.size.y - TILE_SIZE) / 2.0
+}
+
+fn rebuild_nav_grid(terrain: Res<TerrainColliders>, mut navigation: ResMut<Navigation>) {
+    if !terrain.is_changed() {
+        return;
+    }
+    navigation.grids.clear();
+    navigation.generation = navigation.generation.wrapping_add(1);
+}
+
+/// Throws away every path when a level spawns, moves or despawns; they lead through terrain that
+/// is no longer there.
+fn invalidate_paths(
+    mut level_events: EventReader<LevelEvent>,
+    mut agent_query: Query<&mut NavAgent>,
+) {
+    if level_events.iter().count() == 0 {
+        return;
+    }
+    for mut agent in agent_query.iter_mut() {
+        agent.invalidate();
+    }
+}
+
+/// Starts a search for agents whose goal has moved to another cell, or whose path was made on an
+/// older graph. An agent with a search running waits for it first, unless the graph it searches
+/// has been rebuilt since.
+fn request_paths(
+    terrain: Res<TerrainColliders>,
+    mut navigation: ResMut<Navigation>,
+    mut agent_query: Query<(&Transform, &KinematicBody, &mut NavAgent)>,
+) {
+    let task_pool = AsyncComputeTaskPool::get();
+
+    for (transform, body, mut agent) in agent_query.iter_mut() {
+        let Some(goal) = agent.goal else {
+            continue;
+        };
+        if let Some((_, generation)) = agent.searched {
+            if generation != navigation.generation {
+                // The old path is still followed until the new one arrives; only the stale
+                // search is dropped, which cancels it
+                agent.task = None;
+            }
+        }
+        let searched = (TerrainColliders::cell(goal), navigation.generation);
+        if agent.task.is_some() || agent.searched == Some(searched) {
+            continue;
+        }
+
+        let grid = navigation.grid(&terrain, clearance(body));
+        let from = feet(transform.translation.truncate(), body);
+        agent.searched = Some(searched);
+        agent.task = Some(task_pool.spawn(async move { grid.find_path(from, goal) }));
+    }
+}
+
+fn collect_paths(mut agent_query: Query<&mut NavAgent>) {
+    for mut agent in agent_query.iter_mut() {
+        let Some(task) = agent.task.as_mut() else {
+            continue;
+        };
+        let Some(path) = future::block_on(future::poll_once(task)) else {
+            continue;
+        };
+        agent.task = None;
+        agent.path = path;
+        agent.next = 0;
+    }
+}
+
+/// Draws each agent's remaining path, coloured by how it gets to each waypoint.
+fn draw_paths(mut gizmos: Gizmos, agent_query: Query<(&Transform, &NavAgent)>) {
+    for (transform, agent) in agent_query.iter() {
+        let mut from = transform.translation.truncate();
+        for waypoint in agent.remaining() {
+            let color = match waypoint.kind {
+                LinkKind::Walk => WALK_COLOR,
+                LinkKind::Jump => JUMP_COLOR,
+                LinkKind::Drop => DROP_COLOR,
+            };
+            gizmos.line_2d(from, waypoint.position, color);
+            gizmos.circle_2d(waypoint.position, 2.0, color);
+            from = waypoint.position;
+        }
+        if let Some(goal) = agent.goal {
+            gizmos.circle_2d(goal, WAYPOINT_REACHED, Color::WHITE);
+        }
+    }
+}
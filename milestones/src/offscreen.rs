@@ -0,0 +1,188 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+use crate::cam::{camera_follow_system, visible_area};
+use crate::collectible::Collectible;
+use crate::collider::Collider;
+use crate::collision::Aabb;
+use crate::prelude::*;
+use crate::projectile::Projectile;
+use crate::timestep::InterpolatedTransform;
+use crate::GameState;
+
+/// Cleans up whatever the camera has left behind, and recycles frequently spawned sprites.
+///
+/// Entities with [`DespawnWhenOffscreen`] are despawned once they are far enough outside the view.
+/// Kinds registered with [`AddPool::add_pool`] are spawned through a [`Pool`] instead, and handed
+/// back to it rather than despawned.
+pub struct OffscreenPlugin;
+
+impl Plugin for OffscreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            despawn_offscreen
+                .after(camera_follow_system)
+                .run_if(in_state(GameState::Running)),
+        )
+        .add_pool::<Collectible>(COLLECTIBLE_POOL_SIZE)
+        .add_pool::<Projectile>(PROJECTILE_POOL_SIZE);
+    }
+}
+
+/// Removes the entity once it is outside the camera's view by more than the margins. The camera
+/// only scrolls right, so anything left behind is gone for good.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct DespawnWhenOffscreen {
+    /// How far past the left edge of the view it can be.
+    pub behind: f32,
+    /// How far past the right edge, where things are spawned ahead of the player.
+    pub ahead: f32,
+    /// How far past the top and bottom edges.
+    pub vertical: f32,
+}
+
+impl Default for DespawnWhenOffscreen {
+    fn default() -> Self {
+        DespawnWhenOffscreen {
+            behind: OFFSCREEN_MARGIN,
+            ahead: OFFSCREEN_AHEAD_MARGIN,
+            vertical: OFFSCREEN_MARGIN,
+        }
+    }
+}
+
+impl DespawnWhenOffscreen {
+    /// Only once the camera has passed it, for things placed in a level the player hasn't
+    /// reached yet.
+    pub fn left_behind() -> Self {
+        DespawnWhenOffscreen {
+            behind: OFFSCREEN_MARGIN,
+            ahead: f32::INFINITY,
+            vertical: f32::INFINITY,
+        }
+    }
+
+    fn is_outside(&self, view: Aabb, position: Vec2) -> bool {
+        position.x < view.min.x - self.behind
+            || position.x > view.max.x + self.ahead
+            || position.y < view.min.y - self.vertical
+            || position.y > view.max.y + self.vertical
+    }
+}
+
+/// Marks an entity that belongs to a [`Pool`], so it is recycled rather than despawned.
+#[derive(Component)]
+pub struct Pooled;
+
+/// Entities of kind `T` put aside for reuse. Handing one back hides it and strips `T` and its
+/// collider, so no gameplay system sees it until [`Pool::spawn`] fills it in again.
+#[derive(Resource)]
+pub struct Pool<T: Component> {
+    free: Vec<Entity>,
+    /// Most entities kept aside; any more handed back are despawned.
+    capacity: usize,
+    marker: PhantomData<T>,
+}
+
+impl<T: Component> Pool<T> {
+    pub fn new(capacity: usize) -> Self {
+        Pool {
+            free: Vec::with_capacity(capacity),
+            capacity,
+            marker: PhantomData,
+        }
+    }
+
+    /// Inserts `bundle` on an entity from the pool, or a new one if the pool is empty. The bundle
+    /// should include `T` and everything that changes between uses, including the visibility.
+    pub fn spawn(&mut self, commands: &mut Commands, bundle: impl Bundle) -> Entity {
+        // Something else may have despawned a pooled entity, e.g. a level reload
+        let recycled = std::iter::from_fn(|| self.free.pop())
+            .find(|entity| commands.get_entity(*entity).is_some());
+        match recycled {
+            Some(entity) => {
+                commands.entity(entity).insert(bundle);
+                entity
+            }
+            None => commands.spawn((bundle, Pooled)).id(),
+        }
+    }
+
+    /// Puts `entity` aside for the next [`Pool::spawn`].
+    pub fn release(&mut self, commands: &mut Commands, entity: Entity) {
+        // Two systems may let go of the same entity in one tick
+        if self.free.contains(&entity) {
+            return;
+        }
+        if self.free.len() >= self.capacity {
+            commands.entity(entity).despawn();
+            return;
+        }
+        commands
+            .entity(entity)
+            .remove::<(T, Collider, InterpolatedTransform)>()
+            .insert(Visibility::Hidden);
+        self.free.push(entity);
+    }
+}
+
+pub trait AddPool {
+    /// Adds a [`Pool`] for `T` that keeps at most `capacity` entities aside, and hands pooled
+    /// entities of that kind back to it when they go offscreen.
+    fn add_pool<T: Component>(&mut self, capacity: usize) -> &mut Self;
+}
+
+impl AddPool for App {
+    fn add_pool<T: Component>(&mut self, capacity: usize) -> &mut Self {
+        // After gameplay has spawned this frame, so the entities released here are stripped
+        // before anything can take them back out of the pool
+        self.insert_resource(Pool::<T>::new(capacity)).add_systems(
+            PostUpdate,
+            recycle_offscreen::<T>
+                .after(camera_follow_system)
+                .run_if(in_state(GameState::Running)),
+        )
+    }
+}
+
+fn camera_view(
+    camera_query: &Query<(&Transform, &OrthographicProjection), With<Camera>>,
+) -> Option<Aabb> {
+    camera_query
+        .get_single()
+        .ok()
+        .map(|(transform, projection)| visible_area(transform, projection))
+}
+
+pub fn despawn_offscreen(
+    mut commands: Commands,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<Camera>>,
+    offscreen_query: Query<(Entity, &Transform, &DespawnWhenOffscreen), Without<Pooled>>,
+) {
+    let Some(view) = camera_view(&camera_query) else {
+        return;
+    };
+    for (entity, transform, policy) in offscreen_query.iter() {
+        if policy.is_outside(view, transform.translation.truncate()) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn recycle_offscreen<T: Component>(
+    mut commands: Commands,
+    mut pool: ResMut<Pool<T>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<Camera>>,
+    offscreen_query: Query<(Entity, &Transform, &DespawnWhenOffscreen), (With<T>, With<Pooled>)>,
+) {
+    let Some(view) = camera_view(&camera_query) else {
+        return;
+    };
+    for (entity, transform, policy) in offscreen_query.iter() {
+        if policy.is_outside(view, transform.translation.truncate()) {
+            pool.release(&mut commands, entity);
+        }
+    }
+}
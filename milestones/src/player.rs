@@ -1,29 +1,77 @@
+use crate::animation::{AnimationEvent, AnimationSystem, Animator};
+use crate::attack::PlayerAttack;
+use crate::character::SelectedCharacter;
+use crate::collider::Collider;
+use crate::collision::{
+    move_body, Aabb, Contacts, KinematicBody, TerrainCell, TerrainColliders, TerrainKind,
+    STEP_HEIGHT,
+};
+use crate::health::{apply_damage, Died, Health};
+use crate::helpers::json::JsonAssetPlugin;
+use crate::input::{update_action_input, AnalogMovement, PlayerAction};
+use crate::level::PlayerStart;
+use crate::stars::Score;
+use crate::timestep::{InterpolatedTransform, SimulationSet};
+use crate::GameOver;
 use crate::GameState;
 use crate::Platform;
 use crate::PlayerAnimation;
+use bevy::audio::Volume;
 use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
 use bevy::window::PrimaryWindow;
+use serde::Deserialize;
 
 use crate::prelude::*;
 
+/// Clips built from the tags in the Aseprite source, shared by every dino layer.
+const PLAYER_ANIMATION_RULES_PATH: &str = "animations/dino.rules.json";
+/// Playback rate of the walk cycle while running.
+const PLAYER_RUN_ANIMATION_SPEED: f32 = 1.6;
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<PlayerJumpState>()
-            .add_systems(Startup, spawn_player)
-            .add_systems(Update, player_movement.run_if(in_state(GameState::Running)))
+        app.add_plugins(JsonAssetPlugin::<MovementTuning>::new(&["movement.json"]))
+            .register_type::<MovementController>()
+            .init_resource::<PlayerJumpState>()
+            .init_resource::<PlayerInput>()
+            .add_systems(OnExit(GameState::CharacterSelect), spawn_player)
+            .add_systems(Update, apply_movement_tuning)
             .add_systems(
-                Update,
-                confine_player_movement.run_if(in_state(GameState::Running)),
+                PreUpdate,
+                // Gated so confirming a menu entry with the jump button doesn't jump on resume
+                read_player_input
+                    .after(update_action_input)
+                    .run_if(in_state(GameState::Running)),
             )
             .add_systems(
-                Update,
-                player_landing_system.run_if(in_state(GameState::Running)),
+                FixedUpdate,
+                player_movement
+                    .in_set(SimulationSet::Movement)
+                    .run_if(in_state(GameState::Running)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (player_landing_system, confine_player_movement)
+                    .in_set(SimulationSet::Collision)
+                    .run_if(in_state(GameState::Running)),
+            )
+            .add_systems(
+                FixedUpdate,
+                player_death
+                    .after(apply_damage)
+                    .in_set(SimulationSet::Damage)
+                    .run_if(in_state(GameState::Running)),
             )
             .add_systems(
                 Update,
-                player_animation_system.run_if(in_state(GameState::Running)),
+                (
+                    player_animation_system.before(AnimationSystem),
+                    player_animation_sounds.after(AnimationSystem),
+                )
+                    .run_if(in_state(GameState::Running)),
             );
     }
 }
@@ -31,14 +79,6 @@ impl Plugin for PlayerPlugin {
 #[derive(Component)]
 pub struct Player {}
 
-#[derive(Component)]
-pub struct AnimationTimer {
-    pub base_duration: f32,
-    pub elapsed_time: f32,
-    pub frame_count: usize,
-    pub playback_speed: f32,
-}
-
 #[derive(Component)]
 pub struct Velocity {
     pub value: Vec3,
@@ -52,6 +92,7 @@ pub enum PlayerActionState {
     Jumping,
     Running,
     RunningAndJumping,
+    Attacking,
 }
 
 #[derive(Component)]
@@ -59,6 +100,90 @@ pub struct PlayerState {
     pub action_state: PlayerActionState,
 }
 
+/// Horizontal movement tuning, in pixels per second and pixels per second squared.
+///
+/// Loaded from the selected character's tuning file and hot reloaded on save, and also editable
+/// live from the world inspector.
+#[derive(Component, Reflect, Deserialize, Clone, Debug)]
+#[reflect(Component)]
+pub struct MovementController {
+    pub walk_speed: f32,
+    pub run_speed: f32,
+    pub ground_acceleration: f32,
+    pub ground_deceleration: f32,
+    pub ground_turn_acceleration: f32,
+    pub air_acceleration: f32,
+    pub air_deceleration: f32,
+    pub air_turn_acceleration: f32,
+}
+
+impl Default for MovementController {
+    fn default() -> Self {
+        MovementController {
+            walk_speed: PLAYER_SPEED,
+            run_speed: PLAYER_RUN_SPEED,
+            ground_acceleration: 3000.0,
+            ground_deceleration: 4000.0,
+            ground_turn_acceleration: 6000.0,
+            air_acceleration: 1800.0,
+            air_deceleration: 900.0,
+            air_turn_acceleration: 2400.0,
+        }
+    }
+}
+
+impl MovementController {
+    /// New horizontal velocity after accelerating `velocity` towards `direction` for `delta_seconds`.
+    pub fn step(
+        &self,
+        velocity: f32,
+        direction: f32,
+        is_running: bool,
+        is_grounded: bool,
+        delta_seconds: f32,
+    ) -> f32 {
+        let top_speed = if is_running {
+            self.run_speed
+        } else {
+            self.walk_speed
+        };
+        let target = direction * top_speed;
+
+        let (acceleration, deceleration, turn_acceleration) = if is_grounded {
+            (
+                self.ground_acceleration,
+                self.ground_deceleration,
+                self.ground_turn_acceleration,
+            )
+        } else {
+            (
+                self.air_acceleration,
+                self.air_deceleration,
+                self.air_turn_acceleration,
+            )
+        };
+
+        let rate = if direction == 0.0 || velocity.abs() > target.abs() {
+            deceleration
+        } else if velocity != 0.0 && velocity.signum() != direction.signum() {
+            turn_acceleration
+        } else {
+            acceleration
+        };
+
+        let max_change = rate * delta_seconds;
+        velocity + (target - velocity).clamp(-max_change, max_change)
+    }
+}
+
+/// A character's movement profile, loaded from `assets/config/characters`.
+#[derive(Deserialize, TypeUuid, TypePath)]
+#[uuid = "1ea69739-4408-4c81-94e6-a3087ce81ee8"]
+pub struct MovementTuning {
+    pub controller: MovementController,
+    pub jump_force: f32,
+}
+
 #[derive(Resource)]
 pub struct Gravity {
     pub value: f32,
@@ -74,6 +199,15 @@ impl Default for Gravity {
 pub struct PlayerJumpState {
     pub can_jump: bool,
     pub jump_force: f32,
+    /// Seconds after walking off a ledge during which a jump is still allowed.
+    pub coyote_time: f32,
+    /// Seconds a jump pressed in the air is remembered, so it fires on landing.
+    pub jump_buffer_time: f32,
+    /// Multiplier applied to upward velocity when jump is released early, for shorter hops.
+    pub jump_cut_multiplier: f32,
+    pub coyote_timer: f32,
+    pub jump_buffer_timer: f32,
+    pub is_jump_rising: bool,
 }
 
 impl Default for PlayerJumpState {
@@ -81,16 +215,38 @@ impl Default for PlayerJumpState {
         PlayerJumpState {
             can_jump: true,
             jump_force: 500.0,
+            coyote_time: 0.1,
+            jump_buffer_time: 0.12,
+            jump_cut_multiplier: 0.5,
+            coyote_timer: 0.0,
+            jump_buffer_timer: 0.0,
+            is_jump_rising: false,
         }
     }
 }
-use seldom_pixel::prelude::*;
+
+/// Keyboard state sampled every frame and consumed by the fixed-rate simulation.
+///
+/// Presses are latched until a tick reads them, so a jump isn't lost on frames that run no tick
+/// or repeated on frames that run several.
+#[derive(Resource, Default)]
+pub struct PlayerInput {
+    pub direction: f32,
+    pub is_moving: bool,
+    pub is_running: bool,
+    pub is_holding_down: bool,
+    pub jump_pressed: bool,
+    pub jump_held: bool,
+    pub attack_pressed: bool,
+}
 
 fn spawn_player(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     window_query: Query<&Window, With<PrimaryWindow>>,
+    start_query: Query<&GlobalTransform, With<PlayerStart>>,
     asset: Res<PlayerAnimation>, // Assuming PlayerWalk holds the sprite sheet or its handle
+    selected_character: Res<SelectedCharacter>,
 ) {
     // Set up the camera
     //commands.spawn(Camera2dBundle::default());
@@ -103,134 +259,191 @@ fn spawn_player(
         ..Default::default()
     };
 
+    let translation = match start_query.iter().next() {
+        Some(start) => start.translation().truncate().extend(0.0),
+        None => Vec3::new(window.width() / 2.0, window.height() / 2.0, 0.0),
+    };
+
     // Spawn your player entity with the necessary components, including the idle animation attributes
     commands.spawn((
         SpriteSheetBundle {
-            texture_atlas: asset.player.clone(),
-            transform: Transform::from_xyz(window.width() / 2.0, window.height() / 2.0, 0.0),
+            texture_atlas: asset.sheet(selected_character.0),
+            transform: Transform::from_translation(translation),
             sprite,
             ..Default::default()
         },
-        AnimationTimer {
-            base_duration: 0.125,
-            elapsed_time: 0.0,
-            frame_count: 3,
-            playback_speed: 1.0,
-        },
+        Animator::new(asset.animations.clone(), "idle")
+            .with_rules(asset_server.load(PLAYER_ANIMATION_RULES_PATH)),
         PlayerState {
             action_state: PlayerActionState::Idle,
         }, // Initialize with player not running
         Player {},
         Velocity { value: Vec3::ZERO },
+        KinematicBody::new(Vec2::new(PLAYER_HITBOX_WIDTH, PLAYER_HITBOX_HEIGHT)),
+        Contacts::default(),
+        Collider::Aabb {
+            half_extents: Vec2::new(PLAYER_HITBOX_WIDTH, PLAYER_HITBOX_HEIGHT) / 2.0,
+        },
+        InterpolatedTransform::new(translation),
+        MovementController::default(),
+        asset_server.load::<MovementTuning, _>(selected_character.0.tuning_path()),
+        Health::new(PLAYER_MAX_HEALTH, PLAYER_LIVES, PLAYER_INVULNERABILITY_TIME),
+        PlayerAttack::default(),
     ));
 }
 
-fn player_animation_system(
-    time: Res<Time>,
-    mut query: Query<(&mut TextureAtlasSprite, &mut AnimationTimer, &PlayerState)>,
+/// Copies the movement tuning file onto the player whenever it loads or is edited on disk.
+fn apply_movement_tuning(
+    mut tuning_events: EventReader<AssetEvent<MovementTuning>>,
+    tunings: Res<Assets<MovementTuning>>,
+    mut player_query: Query<(&Handle<MovementTuning>, &mut MovementController)>,
+    mut jump_state: ResMut<PlayerJumpState>,
 ) {
-    for (mut sprite, mut animation, player_state) in query.iter_mut() {
-        animation.elapsed_time += time.delta_seconds() * animation.playback_speed;
+    for event in tuning_events.iter() {
+        let (AssetEvent::Created { handle } | AssetEvent::Modified { handle }) = event else {
+            continue;
+        };
+        let Some(tuning) = tunings.get(handle) else {
+            continue;
+        };
+        for (tuning_handle, mut controller) in player_query.iter_mut() {
+            if tuning_handle == handle {
+                *controller = tuning.controller.clone();
+                jump_state.jump_force = tuning.jump_force;
+            }
+        }
+    }
+}
 
-        if animation.elapsed_time >= animation.base_duration {
-            animation.elapsed_time -= animation.base_duration;
+/// Picks the clip for the player's state. The clips are the tags in `DinoSprites.ase`, with the
+/// transitions and frame events in `dino.rules.json`.
+fn player_animation_system(mut query: Query<(&mut Animator, &PlayerState), With<Player>>) {
+    for (mut animator, player_state) in query.iter_mut() {
+        // Running reuses the walk cycle, played faster
+        animator.speed = match player_state.action_state {
+            PlayerActionState::Running => PLAYER_RUN_ANIMATION_SPEED,
+            _ => 1.0,
+        };
+        animator.play(match player_state.action_state {
+            PlayerActionState::Idle => "idle",
+            PlayerActionState::Moving | PlayerActionState::Running => "move",
+            PlayerActionState::Jumping | PlayerActionState::RunningAndJumping => "jump",
+            PlayerActionState::Attacking => "kick",
+        });
+    }
+}
 
-            match player_state.action_state {
-                PlayerActionState::Idle => {
-                    animation.frame_count = 3;
-                    sprite.index = (sprite.index + 1) % animation.frame_count;
-                }
-                PlayerActionState::Moving | PlayerActionState::Running => {
-                    animation.frame_count = 6;
-                    sprite.index = 3 + (sprite.index - 3 + 1) % 6;
-                }
-                PlayerActionState::Jumping | PlayerActionState::RunningAndJumping => {
-                    animation.frame_count = 4;
-                    sprite.index = 5 + (sprite.index - 5 + 1) % 4;
-                }
-            }
+/// Plays footsteps and landings on the animation frames that carry them.
+fn player_animation_sounds(
+    mut commands: Commands,
+    mut animation_events: EventReader<AnimationEvent>,
+    player_query: Query<(), With<Player>>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in animation_events.iter() {
+        if !player_query.contains(event.entity) {
+            continue;
         }
+
+        let (path, volume) = match event.name.as_str() {
+            "footstep" => ("audio/pluck_001.ogg", 0.2),
+            "land" => ("audio/pluck_002.ogg", 0.4),
+            _ => continue,
+        };
+        commands.spawn(AudioBundle {
+            source: asset_server.load(path),
+            settings: PlaybackSettings::DESPAWN.with_volume(Volume::new_relative(volume)),
+        });
     }
 }
 
+pub fn read_player_input(
+    action_input: Res<Input<PlayerAction>>,
+    analog_movement: Res<AnalogMovement>,
+    mut input: ResMut<PlayerInput>,
+) {
+    let left = action_input.pressed(PlayerAction::MoveLeft);
+    let right = action_input.pressed(PlayerAction::MoveRight);
+    let down = action_input.pressed(PlayerAction::MoveDown) || analog_movement.is_holding_down;
+
+    // A tilted stick wins over digital input so partial tilts walk slower
+    input.direction = match (left, right) {
+        _ if analog_movement.direction.x != 0.0 => analog_movement.direction.x,
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => 0.0,
+    };
+    input.is_moving = left || right || down || input.direction != 0.0;
+    input.is_holding_down = down;
+    input.is_running = action_input.pressed(PlayerAction::Run) || analog_movement.is_running;
+    input.jump_pressed |= action_input.just_pressed(PlayerAction::Jump);
+    input.jump_held = action_input.pressed(PlayerAction::Jump);
+    input.attack_pressed |= action_input.just_pressed(PlayerAction::Attack);
+}
+
 pub fn player_movement(
-    keyboard_input: Res<Input<KeyCode>>,
+    mut input: ResMut<PlayerInput>,
     mut player_query: Query<
         (
             &mut Transform,
             &mut PlayerState,
-            &mut AnimationTimer,
             &mut Velocity,
+            &mut KinematicBody,
+            &MovementController,
+            &Contacts,
         ),
         With<Player>,
     >,
+    terrain: Res<TerrainColliders>,
     mut jump_state: ResMut<PlayerJumpState>,
-    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
 ) {
-    if let Ok((mut transform, mut player_state, mut animation, mut velocity)) =
+    if let Ok((mut transform, mut player_state, mut velocity, mut body, controller, contacts)) =
         player_query.get_single_mut()
     {
-        let mut direction = Vec3::ZERO;
-        let is_moving = keyboard_input.pressed(KeyCode::Left)
-            || keyboard_input.pressed(KeyCode::A)
-            || keyboard_input.pressed(KeyCode::Right)
-            || keyboard_input.pressed(KeyCode::D)
-            || keyboard_input.pressed(KeyCode::Down)
-            || keyboard_input.pressed(KeyCode::S);
-
-        // Determine the current direction of the sprite (left or right)
-        let mut facing_right = transform.scale.x > 0.0;
-
-        // If pressing left and sprite was previously facing right, flip the sprite
-        if (keyboard_input.pressed(KeyCode::Left) || keyboard_input.pressed(KeyCode::A))
-            && facing_right
-        {
-            transform.scale.x = -1.0;
-            facing_right = false;
-        }
+        let delta_seconds = fixed_time.period.as_secs_f32();
+        let is_moving = input.is_moving;
+        let is_running = input.is_running;
 
-        // If pressing right and sprite was previously facing left, flip the sprite
-        if (keyboard_input.pressed(KeyCode::Right) || keyboard_input.pressed(KeyCode::D))
-            && !facing_right
-        {
+        // Flip the sprite to face the direction we're heading
+        if input.direction < 0.0 {
+            transform.scale.x = -1.0;
+        } else if input.direction > 0.0 {
             transform.scale.x = 1.0;
-            facing_right = true;
-        }
-
-        if keyboard_input.pressed(KeyCode::Left) || keyboard_input.pressed(KeyCode::A) {
-            direction += Vec3::new(-1.0, 0.0, 0.0);
-        }
-        if keyboard_input.pressed(KeyCode::Right) || keyboard_input.pressed(KeyCode::D) {
-            direction += Vec3::new(1.0, 0.0, 0.0);
-        }
-        if keyboard_input.pressed(KeyCode::Down) || keyboard_input.pressed(KeyCode::S) {
-            direction += Vec3::new(0.0, -1.0, 0.0);
-        }
-
-        if direction.length() > 0.0 {
-            direction = direction.normalize();
         }
 
-        let is_running = keyboard_input.pressed(KeyCode::ShiftLeft)
-            || keyboard_input.pressed(KeyCode::ShiftRight);
-        let speed = if is_running {
-            PLAYER_RUN_SPEED
+        // Horizontal movement goes through velocity so the physics step can block it
+        velocity.value.x = controller.step(
+            velocity.value.x,
+            input.direction,
+            is_running,
+            contacts.on_ground(),
+            delta_seconds,
+        );
+
+        // Landing refills the coyote timer every grounded tick, so it only runs out in the air
+        jump_state.coyote_timer = (jump_state.coyote_timer - delta_seconds).max(0.0);
+        if input.jump_pressed {
+            jump_state.jump_buffer_timer = jump_state.jump_buffer_time;
         } else {
-            PLAYER_SPEED
-        };
-        animation.playback_speed = match (is_running, player_state.action_state.clone()) {
-            (true, PlayerActionState::Jumping) => 0.2,
-            (true, PlayerActionState::Running) => 1.4,
-            (true, PlayerActionState::RunningAndJumping) => 0.6,
-            (false, PlayerActionState::Jumping) => 0.2,
-            _ => 0.8,
-        };
-
-        transform.translation += direction * speed * time.delta_seconds();
-
-        if keyboard_input.just_pressed(KeyCode::Space) && jump_state.can_jump {
-            velocity.value.y += jump_state.jump_force;
+            jump_state.jump_buffer_timer = (jump_state.jump_buffer_timer - delta_seconds).max(0.0);
+        }
+        input.jump_pressed = false;
+
+        let aabb = Aabb::from_center_size(transform.translation.truncate(), body.size);
+        let wants_jump = jump_state.jump_buffer_timer > 0.0;
+        let can_jump = jump_state.can_jump && jump_state.coyote_timer > 0.0;
+
+        if wants_jump && input.is_holding_down && terrain.is_one_way_under(aabb) {
+            // Down+Jump drops through the one-way platform we're standing on
+            body.drop_through = DROP_THROUGH_TIME;
+            jump_state.jump_buffer_timer = 0.0;
+        } else if wants_jump && can_jump {
+            velocity.value.y = jump_state.jump_force;
             jump_state.can_jump = false;
+            jump_state.coyote_timer = 0.0;
+            jump_state.jump_buffer_timer = 0.0;
+            jump_state.is_jump_rising = true;
             if is_running {
                 player_state.action_state = PlayerActionState::RunningAndJumping;
             } else {
@@ -238,9 +451,22 @@ pub fn player_movement(
             }
         }
 
+        // Releasing jump on the way up cuts the jump short
+        if jump_state.is_jump_rising {
+            if velocity.value.y <= 0.0 {
+                jump_state.is_jump_rising = false;
+            } else if !input.jump_held {
+                velocity.value.y *= jump_state.jump_cut_multiplier;
+                jump_state.is_jump_rising = false;
+            }
+        }
+
         // Update player's action state based on their movements
         match player_state.action_state {
-            PlayerActionState::Jumping | PlayerActionState::RunningAndJumping => {} // Do nothing if jumping
+            // Do nothing if jumping or attacking
+            PlayerActionState::Jumping
+            | PlayerActionState::RunningAndJumping
+            | PlayerActionState::Attacking => {}
             _ => {
                 if is_moving {
                     if is_running {
@@ -257,51 +483,91 @@ pub fn player_movement(
 }
 
 pub fn physics_system(
-    mut query: Query<(&mut Transform, &mut Velocity)>,
-    time: Res<Time>,
+    mut query: Query<(
+        &mut Transform,
+        &mut Velocity,
+        Option<&mut KinematicBody>,
+        Option<&mut Contacts>,
+    )>,
+    platform_query: Query<&Transform, (With<Platform>, Without<Velocity>)>,
+    terrain: Res<TerrainColliders>,
+    fixed_time: Res<FixedTime>,
     gravity: Res<Gravity>,
 ) {
-    for (mut transform, mut velocity) in query.iter_mut() {
+    let delta_seconds = fixed_time.period.as_secs_f32();
+    let platform_size = Vec2::new(PLATFORM_WIDTH as f32, PLATFORM_HEIGHT as f32);
+
+    for (mut transform, mut velocity, body, contacts) in query.iter_mut() {
         // Apply gravity to velocity.
-        velocity.value.y += gravity.value * time.delta_seconds();
+        velocity.value.y += gravity.value * delta_seconds;
+
+        let motion = velocity.value * delta_seconds;
+
+        let Some(mut body) = body else {
+            // Apply velocity to transform.
+            transform.translation += motion;
+            continue;
+        };
+
+        body.drop_through = (body.drop_through - delta_seconds).max(0.0);
+        let grounded = contacts
+            .as_ref()
+            .is_some_and(|contacts| contacts.on_ground());
+
+        let aabb = Aabb::from_center_size(transform.translation.truncate(), body.size);
+        let swept_area = aabb
+            .union(&aabb.translated(motion.truncate()))
+            .expanded(STEP_HEIGHT);
+        let cells: Vec<TerrainCell> = platform_query
+            .iter()
+            .map(|transform| TerrainCell {
+                aabb: Aabb::from_center_size(transform.translation.truncate(), platform_size),
+                kind: TerrainKind::Solid,
+            })
+            .filter(|platform| platform.aabb.intersects(&swept_area))
+            .chain(terrain.overlapping(swept_area))
+            .collect();
+
+        let sweep = move_body(
+            aabb,
+            motion.truncate(),
+            &cells,
+            grounded,
+            body.drop_through > 0.0,
+        );
+        transform.translation += sweep.translation.extend(motion.z);
+
+        // Stop moving into whatever we ran into
+        for normal in sweep.normals.iter() {
+            if normal.y.abs() > 0.5 {
+                velocity.value.y = 0.0;
+            } else {
+                velocity.value.x = 0.0;
+            }
+        }
 
-        // Apply velocity to transform.
-        transform.translation += velocity.value * time.delta_seconds();
+        if let Some(mut contacts) = contacts {
+            contacts.normals = sweep.normals;
+        }
     }
 }
 
 pub fn player_landing_system(
-    mut player_query: Query<(&mut Transform, &mut Velocity, &mut PlayerState), With<Player>>,
-    platform_query: Query<&Transform, (With<Platform>, Without<Player>)>,
+    mut player_query: Query<(&Contacts, &mut PlayerState), With<Player>>,
     mut jump_state: ResMut<PlayerJumpState>,
 ) {
-    for (mut player_transform, mut player_velocity, mut player_state) in player_query.iter_mut() {
-        let player_bottom = player_transform.translation.y - PLAYER_SIZE / 2.0;
-
-        for platform_transform in platform_query.iter() {
-            let platform_top = platform_transform.translation.y + PLATFORM_HEIGHT as f32 / 2.0;
-
-            // Bounding box checks (assuming origin is center for both player and platform)
-            let player_left = player_transform.translation.x - PLAYER_SIZE / 2.0;
-            let player_right = player_transform.translation.x + PLAYER_SIZE / 2.0;
-            let platform_left = platform_transform.translation.x - PLATFORM_WIDTH as f32 / 2.0;
-            let platform_right = platform_transform.translation.x + PLATFORM_WIDTH as f32 / 2.0;
-
-            if player_bottom <= platform_top
-                && player_velocity.value.y <= 0.0
-                && player_left < platform_right
-                && player_right > platform_left
-            {
-                player_velocity.value.y = 0.0;
-                player_transform.translation.y = platform_top + PLAYER_SIZE / 2.0; // Adjust so player's bottom aligns with platform top
-                jump_state.can_jump = true;
-
-                // Adjusting player state
-                if player_state.action_state == PlayerActionState::Jumping {
-                    player_state.action_state = PlayerActionState::Idle;
-                }
+    for (contacts, mut player_state) in player_query.iter_mut() {
+        if contacts.on_ground() {
+            jump_state.can_jump = true;
+            jump_state.coyote_timer = jump_state.coyote_time;
+
+            // Adjusting player state
+            if matches!(
+                player_state.action_state,
+                PlayerActionState::Jumping | PlayerActionState::RunningAndJumping
+            ) {
+                player_state.action_state = PlayerActionState::Idle;
             }
-            //println!("{}", player_velocity.value.y);
         }
     }
 }
@@ -345,3 +611,21 @@ pub fn confine_player_movement(
         player_transform.translation = translation;
     }
 }
+
+pub fn player_death(
+    mut commands: Commands,
+    mut died_events: EventReader<Died>,
+    mut game_over_event_writer: EventWriter<GameOver>,
+    player_query: Query<(), With<Player>>,
+    score: Res<Score>,
+) {
+    for died in died_events.iter() {
+        if !player_query.contains(died.entity) {
+            continue;
+        }
+
+        info!("Player is out of lives! Game Over!");
+        commands.entity(died.entity).despawn();
+        game_over_event_writer.send(GameOver { score: score.value });
+    }
+}
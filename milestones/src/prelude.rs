@@ -1,17 +1,33 @@
-use crate::player::Player;
-use bevy::prelude::*;
-
-use crate::player::*;
-
 pub const PLAYER_SPEED: f32 = 300.0; // Regular speed
 pub const PLAYER_RUN_SPEED: f32 = 600.0; // Speed when running
 pub const PLAYER_SIZE: f32 = 64.0; // This is the player sprite size.
-pub const NUMBER_OF_ENEMIES: usize = 4;
+pub const PLAYER_HITBOX_WIDTH: f32 = 12.0; // Collision box used against terrain.
+pub const PLAYER_HITBOX_HEIGHT: f32 = 16.0;
+pub const DROP_THROUGH_TIME: f32 = 0.25; // How long one-way platforms ignore the player after Down+Jump.
+pub const PLAYER_MAX_HEALTH: u32 = 3;
+pub const PLAYER_LIVES: u32 = 2; // Extra refills of health before the game is over.
+pub const PLAYER_INVULNERABILITY_TIME: f32 = 1.5; // Seconds of blinking after a hit.
+pub const PLAYER_KNOCKBACK: f32 = 400.0;
+pub const PLAYER_ATTACK_REACH: f32 = 12.0; // Distance from the player's centre to the kick hitbox.
+pub const PLAYER_ATTACK_WIDTH: f32 = 14.0;
+pub const PLAYER_ATTACK_HEIGHT: f32 = 12.0;
+pub const PLAYER_ATTACK_DAMAGE: u32 = 1;
+pub const PLAYER_ATTACK_KNOCKBACK: f32 = 300.0;
+pub const PLAYER_ATTACK_ACTIVE_START: f32 = 0.1; // Fallback kick timings, for when the clip sends no frame events.
+pub const PLAYER_ATTACK_ACTIVE_END: f32 = 0.2;
+pub const PLAYER_ATTACK_DURATION: f32 = 0.3;
+pub const PLAYER_ATTACK_TIMEOUT: f32 = 1.0; // Longest a kick can last if its clip never finishes.
 pub const PLATFORM_WIDTH: i32 = 1350;
 pub const PLATFORM_HEIGHT: i32 = 10;
-pub const ENEMY_SPEED: f32 = 200.0;
-pub const ENEMY_SIZE: f32 = 64.0; // This is the enemy sprite size.
-pub const NUMBER_OF_STARS: usize = 100;
-pub const STAR_SIZE: f32 = 30.0; // This is the star sprite size.
+pub const ENEMY_SIGHT_RADIUS: f32 = 300.0; // How close the player must be for an enemy to start chasing.
+pub const ENEMY_LOSE_SIGHT_RADIUS: f32 = 450.0; // How far the player must get for a chase to end.
+pub const ENEMY_FLEE_RADIUS: f32 = 150.0;
+pub const ENEMY_SEPARATION_GAP: f32 = 16.0; // Space walkers try to keep between each other.
+pub const ENEMY_SEPARATION_WEIGHT: f32 = 1.5; // How hard walkers steer away from a neighbour touching them.
+pub const PLAYER_STOMP_BOUNCE: f32 = 350.0; // Upward speed after landing on an enemy.
 pub const STAR_SPAWN_TIME: f32 = 0.5;
-pub const ENEMY_SPAWN_TIME: f32 = 3.0;
+pub const STAR_MAX_LIFT: i32 = 3; // Most open cells a star floats above the ground it spawns over.
+pub const OFFSCREEN_MARGIN: f32 = 200.0; // How far past the left, top and bottom of the view things last.
+pub const OFFSCREEN_AHEAD_MARGIN: f32 = 1500.0; // Pickups and enemies are spawned ahead, so allow more there.
+pub const COLLECTIBLE_POOL_SIZE: usize = 64;
+pub const PROJECTILE_POOL_SIZE: usize = 128;
@@ -0,0 +1,312 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::behaviour::{BehaviourSet, Senses};
+use crate::collider::{Collider, SpatialHash};
+use crate::collision::{Aabb, TerrainColliders, TerrainKind};
+use crate::enemy::Enemy;
+use crate::health::Damage;
+use crate::offscreen::{DespawnWhenOffscreen, Pool};
+use crate::timestep::{InterpolatedTransform, SimulationSet};
+use crate::GameState;
+use crate::Player;
+
+/// Shots that fly in a straight line until they hit something on the other team, hit solid
+/// terrain, or run out of time.
+///
+/// Anything can fire a [`Weapon`] with [`fire`]; enemies with a [`RangedAttack`] do so on their own
+/// whenever they can see the player in range.
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                enemy_fire
+                    .after(BehaviourSet::Sense)
+                    .in_set(SimulationSet::Movement),
+                move_projectiles.in_set(SimulationSet::Physics),
+                projectile_hit_targets.in_set(SimulationSet::Collision),
+            )
+                .run_if(in_state(GameState::Running)),
+        );
+    }
+}
+
+/// Which side fired a projectile. Projectiles only hit the other side.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Team {
+    // Nothing on the player's side shoots yet
+    #[allow(dead_code)]
+    Player,
+    Enemy,
+}
+
+#[derive(Component, Debug)]
+pub struct Projectile {
+    /// Pixels per second.
+    pub velocity: Vec2,
+    /// Seconds left before it disappears.
+    pub lifetime: f32,
+    pub team: Team,
+    pub damage: u32,
+    pub knockback: f32,
+    /// Whoever fired it, if they are still around.
+    pub owner: Option<Entity>,
+}
+
+/// How the shots of one volley are laid out around the aim direction.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub enum FirePattern {
+    /// A single shot straight at the target.
+    #[default]
+    Aimed,
+    /// `count` shots fanned evenly across `angle` degrees, centred on the target.
+    Spread { count: u32, angle: f32 },
+    /// `count` shots evenly around a full circle, starting at the target.
+    Radial { count: u32 },
+}
+
+impl FirePattern {
+    /// Unit directions of each shot for a volley aimed along `aim`.
+    pub fn directions(&self, aim: Vec2) -> Vec<Vec2> {
+        let aim = aim.try_normalize().unwrap_or(Vec2::X);
+        match *self {
+            FirePattern::Aimed => vec![aim],
+            FirePattern::Spread { count, angle } => {
+                if count <= 1 {
+                    return vec![aim];
+                }
+                let step = angle.to_radians() / (count - 1) as f32;
+                let first = -angle.to_radians() / 2.0;
+                (0..count)
+                    .map(|shot| Vec2::from_angle(first + step * shot as f32).rotate(aim))
+                    .collect()
+            }
+            FirePattern::Radial { count } => {
+                let step = std::f32::consts::TAU / count.max(1) as f32;
+                (0..count.max(1))
+                    .map(|shot| Vec2::from_angle(step * shot as f32).rotate(aim))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Everything about a kind of shot and how it is fired, as written in an archetype file.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Weapon {
+    pub pattern: FirePattern,
+    /// Pixels per second.
+    pub speed: f32,
+    /// Seconds before a shot that hit nothing disappears.
+    pub lifetime: f32,
+    pub damage: u32,
+    pub knockback: f32,
+    pub radius: f32,
+    /// Sprite path relative to `assets`, tinted with `color`.
+    pub sprite: String,
+    pub color: [f32; 3],
+    /// Played once per volley.
+    pub sound: Option<String>,
+    /// Seconds between volleys.
+    pub cooldown: f32,
+    /// Enemies only fire at a player closer than this.
+    pub range: f32,
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Weapon {
+            pattern: FirePattern::Aimed,
+            speed: 250.0,
+            lifetime: 3.0,
+            damage: 1,
+            knockback: 250.0,
+            radius: 6.0,
+            sprite: "sprites/ball_blue_large.png".to_string(),
+            color: [1.0, 1.0, 1.0],
+            sound: None,
+            cooldown: 2.0,
+            range: 350.0,
+        }
+    }
+}
+
+/// Who fires a volley, and from where.
+#[derive(Copy, Clone, Debug)]
+pub struct Shooter {
+    pub team: Team,
+    pub entity: Option<Entity>,
+    pub position: Vec2,
+}
+
+/// Fires one volley of `weapon` from `shooter` towards `aim`.
+pub fn fire(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    pool: &mut Pool<Projectile>,
+    weapon: &Weapon,
+    shooter: Shooter,
+    aim: Vec2,
+) {
+    let [r, g, b] = weapon.color;
+    let origin = shooter.position.extend(0.0);
+    for direction in weapon.pattern.directions(aim) {
+        let bundle = (
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(r, g, b),
+                    custom_size: Some(Vec2::splat(weapon.radius * 2.0)),
+                    ..default()
+                },
+                transform: Transform::from_translation(origin),
+                texture: asset_server.load(&weapon.sprite),
+                ..default()
+            },
+            Projectile {
+                velocity: direction * weapon.speed,
+                lifetime: weapon.lifetime,
+                team: shooter.team,
+                damage: weapon.damage,
+                knockback: weapon.knockback,
+                owner: shooter.entity,
+            },
+            Collider::Circle {
+                radius: weapon.radius,
+            },
+            InterpolatedTransform::new(origin),
+            DespawnWhenOffscreen::default(),
+        );
+        pool.spawn(commands, bundle);
+    }
+
+    if let Some(sound) = &weapon.sound {
+        commands.spawn(AudioBundle {
+            source: asset_server.load(sound),
+            settings: PlaybackSettings::DESPAWN,
+        });
+    }
+}
+
+/// Lets an enemy shoot at the player it can see.
+#[derive(Component, Debug)]
+pub struct RangedAttack {
+    pub weapon: Weapon,
+    cooldown_timer: f32,
+}
+
+impl RangedAttack {
+    pub fn new(weapon: Weapon) -> Self {
+        // Don't fire the moment it spawns
+        let cooldown_timer = weapon.cooldown;
+        RangedAttack {
+            weapon,
+            cooldown_timer,
+        }
+    }
+}
+
+pub fn enemy_fire(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    fixed_time: Res<FixedTime>,
+    mut projectile_pool: ResMut<Pool<Projectile>>,
+    mut enemy_query: Query<(Entity, &Transform, &Senses, &mut RangedAttack), With<Enemy>>,
+) {
+    let delta_seconds = fixed_time.period.as_secs_f32();
+
+    for (entity, transform, senses, mut ranged) in enemy_query.iter_mut() {
+        ranged.cooldown_timer = (ranged.cooldown_timer - delta_seconds).max(0.0);
+        let Some(to_player) = senses.to_player else {
+            continue;
+        };
+        if ranged.cooldown_timer > 0.0
+            || !senses.player_visible
+            || to_player.length() > ranged.weapon.range
+        {
+            continue;
+        }
+
+        ranged.cooldown_timer = ranged.weapon.cooldown;
+        let shooter = Shooter {
+            team: Team::Enemy,
+            entity: Some(entity),
+            position: transform.translation.truncate(),
+        };
+        fire(
+            &mut commands,
+            &asset_server,
+            &mut projectile_pool,
+            &ranged.weapon,
+            shooter,
+            to_player,
+        );
+    }
+}
+
+/// Moves projectiles and removes the ones that hit solid terrain or expired.
+pub fn move_projectiles(
+    mut commands: Commands,
+    fixed_time: Res<FixedTime>,
+    terrain: Res<TerrainColliders>,
+    mut projectile_pool: ResMut<Pool<Projectile>>,
+    mut projectile_query: Query<(Entity, &mut Transform, &mut Projectile)>,
+) {
+    let delta_seconds = fixed_time.period.as_secs_f32();
+
+    for (entity, mut transform, mut projectile) in projectile_query.iter_mut() {
+        projectile.lifetime -= delta_seconds;
+        let from = transform.translation.truncate();
+        let to = from + projectile.velocity * delta_seconds;
+
+        // Slopes stop shots too, though they don't block sight
+        let into_slope = terrain
+            .overlapping(Aabb::from_center_size(to, Vec2::ONE))
+            .any(|tile| matches!(tile.kind, TerrainKind::Slope { .. }));
+        if projectile.lifetime <= 0.0 || into_slope || !terrain.line_of_sight(from, to) {
+            // Still around until commands apply, so mark it spent for the hit check
+            projectile.lifetime = 0.0;
+            projectile_pool.release(&mut commands, entity);
+            continue;
+        }
+        transform.translation = to.extend(transform.translation.z);
+    }
+}
+
+/// Damages the first target on the other team a projectile touches, and removes it.
+pub fn projectile_hit_targets(
+    mut commands: Commands,
+    mut damage_events: EventWriter<Damage>,
+    spatial_hash: Res<SpatialHash>,
+    mut projectile_pool: ResMut<Pool<Projectile>>,
+    projectile_query: Query<(Entity, &Transform, &Projectile)>,
+    player_query: Query<(), With<Player>>,
+    enemy_query: Query<(), With<Enemy>>,
+) {
+    for (entity, transform, projectile) in projectile_query.iter() {
+        if projectile.lifetime <= 0.0 {
+            continue;
+        }
+        let target = spatial_hash.overlapping(entity).find(|&other| {
+            let on_other_team = match projectile.team {
+                Team::Player => enemy_query.contains(other),
+                Team::Enemy => player_query.contains(other),
+            };
+            on_other_team && Some(other) != projectile.owner
+        });
+        let Some(target) = target else {
+            continue;
+        };
+
+        damage_events.send(Damage {
+            target,
+            amount: projectile.damage,
+            source: transform.translation.truncate(),
+            knockback: projectile.knockback,
+        });
+        projectile_pool.release(&mut commands, entity);
+    }
+}
@@ -1,8 +1,10 @@
-use bevy::audio::PlaybackMode;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use rand::prelude::*;
 
+use crate::collectible::{spawn_collectible, Collectible, CollectibleAssets, CollectibleRegistry};
+use crate::collision::TerrainColliders;
+use crate::offscreen::Pool;
 use crate::GameState;
 use crate::Player;
 
@@ -15,8 +17,6 @@ impl Plugin for StarPlugin {
         app.init_resource::<StarSpawnTimer>()
             .init_resource::<Score>()
             .init_resource::<HighScores>()
-            .add_systems(Startup, spawn_stars.run_if(in_state(GameState::Running)))
-            .add_systems(Update, player_hit_star.run_if(in_state(GameState::Running)))
             .add_systems(Update, update_score.run_if(in_state(GameState::Running)))
             .add_systems(
                 Update,
@@ -34,30 +34,20 @@ pub fn update_score(score: Res<Score>) {
         println!("Score: {}", score.value);
     }
 }
-#[derive(Resource)]
+#[derive(Resource, Default)]
 pub struct Score {
     pub value: u32,
 }
 
-impl Default for Score {
-    fn default() -> Score {
-        Score { value: 0 }
-    }
-}
-
-#[derive(Resource, Debug)]
+#[derive(Resource, Debug, Default)]
 pub struct HighScores {
+    // Not shown anywhere yet
+    #[allow(dead_code)]
     pub scores: Vec<(String, u32)>,
 }
 
-impl Default for HighScores {
-    fn default() -> HighScores {
-        HighScores { scores: Vec::new() }
-    }
-}
-
-#[derive(Component)]
-pub struct Star {}
+/// Kind in `default.collectibles.json` spawned ahead of the player over time.
+const STAR_KIND: &str = "star";
 
 #[derive(Resource)]
 pub struct StarSpawnTimer {
@@ -72,38 +62,6 @@ impl Default for StarSpawnTimer {
     }
 }
 
-pub fn spawn_stars(
-    mut commands: Commands,
-    window_query: Query<&Window, (With<PrimaryWindow>, Without<Player>)>,
-    asset_server: Res<AssetServer>,
-    player_query: Query<&Transform, (With<Player>, Without<PrimaryWindow>)>,
-) {
-    let window = window_query.get_single().unwrap();
-    let player_transform = if let Ok(transform) = player_query.get_single() {
-        transform
-    } else {
-        return; // If player doesn't exist, exit the function early.
-    };
-    let player_position = player_transform.translation;
-
-    // Use an offset to spawn stars to the right of the player
-    const OFFSET: f32 = 100.0;
-
-    for _ in 0..NUMBER_OF_STARS {
-        let random_x = player_position.x + OFFSET + (random::<f32>() * window.width());
-        let random_y = random::<f32>() * window.height();
-
-        commands.spawn((
-            SpriteBundle {
-                transform: Transform::from_xyz(random_x, random_y, 0.0),
-                texture: asset_server.load("sprites/star.png"),
-                ..default()
-            },
-            Star {},
-        ));
-    }
-}
-
 pub fn tick_star_spawn_timer(mut star_spawn_timer: ResMut<StarSpawnTimer>, time: Res<Time>) {
     star_spawn_timer.timer.tick(time.delta());
 }
@@ -113,8 +71,15 @@ pub fn spawn_stars_over_time(
     window_query: Query<&Window, (With<PrimaryWindow>, Without<Player>)>,
     asset_server: Res<AssetServer>,
     star_spawn_timer: Res<StarSpawnTimer>,
+    collectible_assets: Res<CollectibleAssets>,
+    registries: Res<Assets<CollectibleRegistry>>,
+    mut collectible_pool: ResMut<Pool<Collectible>>,
     player_query: Query<&Transform, (With<Player>, Without<PrimaryWindow>)>,
+    terrain: Res<TerrainColliders>,
 ) {
+    let Some(registry) = registries.get(&collectible_assets.registry) else {
+        return;
+    };
     if star_spawn_timer.timer.finished() {
         let window = window_query.get_single().unwrap();
         let player_transform = if let Ok(transform) = player_query.get_single() {
@@ -128,48 +93,27 @@ pub fn spawn_stars_over_time(
         const OFFSET: f32 = 100.0;
 
         let random_x = player_position.x + OFFSET + (random::<f32>() * window.width());
-        let random_y = ((random::<f32>() * window.height()) / 5.0) + 40.0;
-
-        commands.spawn((
-            SpriteBundle {
-                transform: Transform::from_xyz(random_x, random_y, 0.0),
-                texture: asset_server.load("sprites/star.png"),
-                ..default()
-            },
-            Star {},
-        ));
-    }
-}
-
-pub fn player_hit_star(
-    mut commands: Commands,
-    player_query: Query<&Transform, With<Player>>,
-    star_query: Query<(Entity, &Transform), With<Star>>,
-    asset_server: Res<AssetServer>,
-    mut score: ResMut<Score>,
-) {
-    if let Ok(player_transform) = player_query.get_single() {
-        for (star_entity, star_transform) in star_query.iter() {
-            let distance = player_transform
-                .translation
-                .distance(star_transform.translation);
-
-            if distance < PLAYER_SIZE / 2.0 + STAR_SIZE / 2.0 {
-                println!("Player hit star!");
-                score.value += 1;
-                let sound_effect = asset_server.load("audio/laserLarge_000.ogg");
-
-                // Play the sound effect.
-                commands.spawn(AudioBundle {
-                    source: sound_effect,
-                    settings: PlaybackSettings {
-                        mode: PlaybackMode::Despawn,
-                        ..default()
-                    },
-                });
-
-                commands.entity(star_entity).despawn();
+        // Sit on whatever ground is there, lifted a few open cells at most
+        let top = player_position.y + window.height() / 2.0;
+        let bottom = player_position.y - window.height();
+        let Some(mut cell) = terrain.open_cell_above_ground(random_x, top, bottom) else {
+            return; // Over a pit, or past the end of the level.
+        };
+        for _ in 0..thread_rng().gen_range(0..=STAR_MAX_LIFT) {
+            if terrain.tile(cell + IVec2::Y).is_some() {
+                break;
             }
+            cell += IVec2::Y;
         }
+        let random_y = TerrainColliders::cell_center(cell).y;
+
+        spawn_collectible(
+            &mut commands,
+            &asset_server,
+            &mut collectible_pool,
+            registry,
+            STAR_KIND,
+            Vec2::new(random_x, random_y),
+        );
     }
 }
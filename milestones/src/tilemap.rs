@@ -1,9 +1,6 @@
 // tilemap.rs
 use bevy::prelude::*;
-use bevy::reflect::Map;
 use bevy_ecs_ldtk::LdtkWorldBundle;
-use bevy_ecs_ldtk::*;
-use bevy_ecs_tilemap::prelude::*;
 
 pub struct TilemapPlugin;
 
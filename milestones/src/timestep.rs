@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+/// Rate the gameplay simulation is stepped at, independent of the render frame rate.
+pub const SIMULATION_HZ: f32 = 60.0;
+
+pub struct FixedTimestepPlugin;
+
+impl Plugin for FixedTimestepPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FixedTime::new_from_secs(1.0 / SIMULATION_HZ))
+            .configure_sets(
+                FixedUpdate,
+                (
+                    SimulationSet::Movement,
+                    SimulationSet::Physics,
+                    SimulationSet::Broadphase,
+                    SimulationSet::Collision,
+                    SimulationSet::Damage,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    restore_simulated_transforms.before(SimulationSet::Movement),
+                    snapshot_simulated_transforms.after(SimulationSet::Damage),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                interpolate_transforms.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// Ordering of the gameplay systems that run inside `FixedUpdate`.
+#[derive(Debug, Clone, Copy, SystemSet, PartialEq, Eq, Hash)]
+pub enum SimulationSet {
+    /// Turns input and AI decisions into velocities.
+    Movement,
+    /// Integrates velocities and sweeps bodies against terrain.
+    Physics,
+    /// Finds overlapping colliders and sends overlap events.
+    Broadphase,
+    /// Reacts to the new positions: landing, boundaries, hits.
+    Collision,
+    /// Applies the damage sent during `Collision` and resolves deaths.
+    Damage,
+}
+
+/// Simulated positions from the last two fixed ticks of an entity.
+///
+/// The simulation always works on `current`; `Transform` is only blended between the two for
+/// rendering, so anything moving an entity should do so inside `FixedUpdate`.
+#[derive(Component)]
+pub struct InterpolatedTransform {
+    pub previous: Vec3,
+    pub current: Vec3,
+}
+
+impl InterpolatedTransform {
+    pub fn new(translation: Vec3) -> Self {
+        InterpolatedTransform {
+            previous: translation,
+            current: translation,
+        }
+    }
+}
+
+/// Puts the simulated position back before a tick so the simulation never sees a blended one.
+pub fn restore_simulated_transforms(mut query: Query<(&mut Transform, &InterpolatedTransform)>) {
+    for (mut transform, interpolated) in query.iter_mut() {
+        transform.translation = interpolated.current;
+    }
+}
+
+pub fn snapshot_simulated_transforms(mut query: Query<(&Transform, &mut InterpolatedTransform)>) {
+    for (transform, mut interpolated) in query.iter_mut() {
+        interpolated.previous = interpolated.current;
+        interpolated.current = transform.translation;
+    }
+}
+
+/// Blends `Transform` between the last two ticks by how far we are into the next one.
+pub fn interpolate_transforms(
+    fixed_time: Res<FixedTime>,
+    mut query: Query<(&mut Transform, &InterpolatedTransform)>,
+) {
+    let alpha = (fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32()).min(1.0);
+
+    for (mut transform, interpolated) in query.iter_mut() {
+        transform.translation = interpolated.previous.lerp(interpolated.current, alpha);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::collision::{Contacts, KinematicBody, TerrainColliders, TILE_SIZE};
+    use crate::player::{
+        physics_system, player_landing_system, player_movement, Gravity, MovementController,
+        Player, PlayerActionState, PlayerInput, PlayerJumpState, PlayerState, Velocity,
+    };
+    use crate::Platform;
+
+    /// Tick the jump is pressed on, after the player has settled on the floor.
+    const JUMP_TICK: u32 = 10;
+    /// Ticks simulated per run, enough to land well before the end.
+    const TICKS: u32 = 120;
+    const START: Vec3 = Vec3::new(0.0, 13.0, 0.0);
+
+    /// What the player did, counted in fixed ticks so every frame rate can be compared.
+    #[derive(Resource, Default, Debug, PartialEq)]
+    struct JumpTrace {
+        tick: u32,
+        apex: f32,
+        landing: Option<(u32, Vec3)>,
+    }
+
+    /// Runs right while holding jump, pressing it once on `JUMP_TICK`.
+    fn drive_input(mut trace: ResMut<JumpTrace>, mut input: ResMut<PlayerInput>) {
+        trace.tick += 1;
+        input.direction = 1.0;
+        input.is_moving = true;
+        input.jump_held = true;
+        input.jump_pressed = trace.tick == JUMP_TICK;
+    }
+
+    fn record_jump(
+        mut trace: ResMut<JumpTrace>,
+        player_query: Query<(&Transform, &Contacts), With<Player>>,
+    ) {
+        let Ok((transform, contacts)) = player_query.get_single() else {
+            return;
+        };
+        if trace.tick <= JUMP_TICK || trace.tick > TICKS || trace.landing.is_some() {
+            return;
+        }
+        trace.apex = trace.apex.max(transform.translation.y);
+        if contacts.on_ground() && trace.apex > START.y {
+            trace.landing = Some((trace.tick, transform.translation));
+        }
+    }
+
+    /// Jumps the player across a flat floor, rendering at `frame_time` seconds a frame.
+    fn jump_at_frame_time(frame_time: f64) -> JumpTrace {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, FixedTimestepPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+                frame_time,
+            )))
+            .init_resource::<JumpTrace>()
+            .init_resource::<PlayerInput>()
+            .init_resource::<PlayerJumpState>()
+            .init_resource::<TerrainColliders>()
+            .init_resource::<Gravity>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    drive_input.before(SimulationSet::Movement),
+                    player_movement.in_set(SimulationSet::Movement),
+                    physics_system.in_set(SimulationSet::Physics),
+                    (player_landing_system, record_jump)
+                        .chain()
+                        .in_set(SimulationSet::Collision),
+                ),
+            );
+
+        app.world
+            .spawn((Platform {}, Transform::from_translation(Vec3::ZERO)));
+        app.world.spawn((
+            Player {},
+            Transform::from_translation(START),
+            PlayerState {
+                action_state: PlayerActionState::Idle,
+            },
+            Velocity { value: Vec3::ZERO },
+            KinematicBody::new(Vec2::new(12.0, 16.0)),
+            Contacts::default(),
+            MovementController::default(),
+        ));
+
+        while app.world.resource::<JumpTrace>().tick < TICKS {
+            app.update();
+        }
+        app.world.remove_resource::<JumpTrace>().unwrap()
+    }
+
+    #[test]
+    fn jump_is_the_same_at_any_frame_rate() {
+        let reference = jump_at_frame_time(1.0 / 60.0);
+        assert!(reference.apex > START.y + TILE_SIZE, "{reference:?}");
+        let Some((_, landed_at)) = reference.landing else {
+            panic!("never landed: {reference:?}");
+        };
+        assert!(landed_at.x > START.x, "{reference:?}");
+
+        for frame_time in [1.0 / 30.0, 1.0 / 47.0, 1.0 / 144.0, 1.0 / 240.0] {
+            let mut trace = jump_at_frame_time(frame_time);
+            // Big frames can run a few ticks past the end in their last update
+            trace.tick = reference.tick;
+            assert_eq!(trace, reference, "rendering every {frame_time}s");
+        }
+    }
+}
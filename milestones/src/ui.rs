@@ -1,23 +1,63 @@
 use bevy::app::App;
+use bevy::app::AppExit;
 use bevy::app::Plugin;
 use bevy::app::Startup;
 use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
-use bevy::input::keyboard::KeyCode;
 use bevy::prelude::*;
 use bevy::render::view::Visibility;
 
+use crate::input::PlayerAction;
 use crate::GameState;
 
+const BUTTON_COLOR: Color = Color::rgb(0.1, 0.2, 0.3);
+const SELECTED_BUTTON_COLOR: Color = Color::rgb(0.25, 0.4, 0.55);
+
 #[derive(Component)]
 struct Menu;
 
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+enum MenuButton {
+    Resume,
+    Quit,
+}
+
+impl MenuButton {
+    /// Top to bottom, the order the d-pad walks through them.
+    const ORDER: [MenuButton; 2] = [MenuButton::Resume, MenuButton::Quit];
+
+    fn label(self) -> &'static str {
+        match self {
+            MenuButton::Resume => "Resume Game",
+            MenuButton::Quit => "Quit",
+        }
+    }
+}
+
+/// Index into `MenuButton::ORDER` of the button highlighted for gamepad and keyboard navigation.
+#[derive(Resource, Default)]
+struct MenuSelection {
+    index: usize,
+}
+
 pub struct UIPlugin;
 
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<MenuSelection>();
         app.add_systems(Startup, spawn_menu);
         app.add_systems(Startup, spawn_fps_text);
-        app.add_systems(Update, (toggle_menu_visibility, resume_button));
+        app.add_systems(Update, (resume_button, exit_game));
+        app.add_systems(
+            Update,
+            toggle_menu_visibility
+                .run_if(in_state(GameState::Running).or_else(in_state(GameState::Paused))),
+        );
+        app.add_systems(
+            Update,
+            navigate_menu
+                .after(toggle_menu_visibility)
+                .run_if(in_state(GameState::Paused)),
+        );
         app.add_systems(
             Update,
             fps_display_system.run_if(in_state(GameState::Running)),
@@ -76,60 +116,61 @@ fn spawn_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                         font: font.clone(),
                         font_size: 32.0,
                         color: Color::WHITE,
-                        ..Default::default()
                     },
                 ));
             });
 
-            // Spawn the button
-            let mut button_entity = parent.spawn((
-                ButtonBundle {
-                    node: Node::default(),
-                    button: Button::default(),
-                    style: Style {
-                        top: Val::Percent(50.0),
-                        width: Val::Percent(20.0),
-                        height: Val::Percent(8.0),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        position_type: PositionType::Absolute,
-                        ..Default::default()
-                    },
-                    interaction: Interaction::default(),
-                    background_color: BackgroundColor(Color::rgb(0.1, 0.2, 0.3)),
-                    border_color: BorderColor(Color::rgb(0.4, 0.5, 0.6)),
-                    image: UiImage::default(),
-                    transform: Transform::default(),
-                    global_transform: GlobalTransform::default(),
-                    visibility: Visibility::default(),
-                    computed_visibility: ComputedVisibility::default(),
-                    z_index: ZIndex::default(),
-                    ..Default::default()
-                },
-                Name::new("Button"),
-            ));
-
-            // Add text to the button
-            button_entity.with_children(|parent| {
-                parent.spawn(TextBundle::from_section(
-                    "Resume Game",
-                    TextStyle {
-                        font: font.clone(),
-                        font_size: 24.0,
-                        color: Color::WHITE,
+            // Spawn a button for each menu entry, stacked downwards
+            for (index, menu_button) in MenuButton::ORDER.into_iter().enumerate() {
+                let mut button_entity = parent.spawn((
+                    ButtonBundle {
+                        node: Node::default(),
+                        button: Button,
+                        style: Style {
+                            top: Val::Percent(50.0 + 12.0 * index as f32),
+                            width: Val::Percent(20.0),
+                            height: Val::Percent(8.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            position_type: PositionType::Absolute,
+                            ..Default::default()
+                        },
+                        interaction: Interaction::default(),
+                        background_color: BackgroundColor(BUTTON_COLOR),
+                        border_color: BorderColor(Color::rgb(0.4, 0.5, 0.6)),
+                        image: UiImage::default(),
+                        transform: Transform::default(),
+                        global_transform: GlobalTransform::default(),
+                        visibility: Visibility::default(),
+                        computed_visibility: ComputedVisibility::default(),
+                        z_index: ZIndex::default(),
                         ..Default::default()
                     },
+                    Name::new("Button"),
+                    menu_button,
                 ));
-            });
+
+                // Add text to the button
+                button_entity.with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        menu_button.label(),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 24.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+                });
+            }
         });
 }
 
 fn toggle_menu_visibility(
     mut app_state: ResMut<State<GameState>>,
-    keyboard_input: Res<Input<KeyCode>>,
+    action_input: Res<Input<PlayerAction>>,
     mut query: Query<&mut Visibility, With<Menu>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::P) {
+    if action_input.just_pressed(PlayerAction::Pause) {
         let mut visibility = query.single_mut();
         match *visibility {
             Visibility::Visible => {
@@ -146,27 +187,28 @@ fn toggle_menu_visibility(
     }
 }
 
+fn exit_game(action_input: Res<Input<PlayerAction>>, mut app_exit_events: EventWriter<AppExit>) {
+    if action_input.just_pressed(PlayerAction::Exit) {
+        app_exit_events.send(AppExit);
+    }
+}
+
 fn resume_button(
-    button: Query<(&Button, &Interaction), With<Button>>,
+    button: Query<(&MenuButton, &Interaction), Changed<Interaction>>,
     mut visibility: Query<&mut Visibility, With<Menu>>,
     mut app_state: ResMut<State<GameState>>,
+    mut app_exit_events: EventWriter<AppExit>,
 ) {
-    for (_button, interaction) in button.iter() {
+    for (menu_button, interaction) in button.iter() {
         match *interaction {
             Interaction::Pressed => {
                 let mut visibility = visibility.single_mut();
-                match *visibility {
-                    Visibility::Visible => {
-                        *visibility = Visibility::Hidden;
-                        *app_state = State::new(GameState::Running); // Resume the game
-                    }
-                    Visibility::Hidden | Visibility::Inherited => {
-                        *visibility = Visibility::Visible;
-                        *app_state = State::new(GameState::Paused); // Pause the game
-                    }
-                }
-
-                // Execute your desired code here
+                activate_menu_button(
+                    *menu_button,
+                    &mut visibility,
+                    &mut app_state,
+                    &mut app_exit_events,
+                );
             }
             Interaction::Hovered => {
                 // Optionally, handle the hover state if you want
@@ -178,6 +220,59 @@ fn resume_button(
     }
 }
 
+fn activate_menu_button(
+    menu_button: MenuButton,
+    visibility: &mut Visibility,
+    app_state: &mut State<GameState>,
+    app_exit_events: &mut EventWriter<AppExit>,
+) {
+    match menu_button {
+        MenuButton::Resume => {
+            *visibility = Visibility::Hidden;
+            *app_state = State::new(GameState::Running); // Resume the game
+        }
+        MenuButton::Quit => app_exit_events.send(AppExit),
+    }
+}
+
+/// Moves the highlight between the menu buttons with the d-pad or arrow keys and presses the
+/// highlighted one on confirm.
+fn navigate_menu(
+    action_input: Res<Input<PlayerAction>>,
+    mut selection: ResMut<MenuSelection>,
+    mut button_query: Query<(&MenuButton, &mut BackgroundColor)>,
+    mut visibility: Query<&mut Visibility, With<Menu>>,
+    mut app_state: ResMut<State<GameState>>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    let button_count = MenuButton::ORDER.len();
+    if action_input.just_pressed(PlayerAction::MenuDown) {
+        selection.index = (selection.index + 1) % button_count;
+    }
+    if action_input.just_pressed(PlayerAction::MenuUp) {
+        selection.index = (selection.index + button_count - 1) % button_count;
+    }
+
+    let selected = MenuButton::ORDER[selection.index];
+    for (menu_button, mut background_color) in button_query.iter_mut() {
+        *background_color = if *menu_button == selected {
+            BackgroundColor(SELECTED_BUTTON_COLOR)
+        } else {
+            BackgroundColor(BUTTON_COLOR)
+        };
+    }
+
+    if action_input.just_pressed(PlayerAction::MenuConfirm) {
+        let mut visibility = visibility.single_mut();
+        activate_menu_button(
+            selected,
+            &mut visibility,
+            &mut app_state,
+            &mut app_exit_events,
+        );
+    }
+}
+
 // Don't forget to add the system to your AppBuilder when setting up your Bevy app
 // app.add_system(button_system.system());
 #[derive(Component)]
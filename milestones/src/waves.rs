@@ -0,0 +1,351 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::{HashMap, HashSet};
+use bevy_asset_loader::prelude::*;
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::archetype::{spawn_enemy, EnemyArchetypes, EnemyAssets};
+use crate::cam::visible_area;
+use crate::collision::{KinematicBody, TerrainColliders};
+use crate::enemy::{random_spawn_position, Enemy};
+use crate::health::Died;
+use crate::helpers::json::JsonAssetPlugin;
+use crate::navigation::{clearance, Navigation};
+use crate::GameState;
+use crate::Player;
+
+/// Sends enemies in waves read from `default.waves.json`: the authored waves in order, then
+/// procedural ones that keep getting harder. Each wave is followed by a rest once it is cleared.
+pub struct SpawnDirectorPlugin;
+
+impl Plugin for SpawnDirectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(JsonAssetPlugin::<WaveSchedule>::new(&["waves.json"]));
+
+        app.init_collection::<WaveAssets>()
+            .init_resource::<SpawnDirector>()
+            .add_event::<WaveCleared>()
+            .add_systems(
+                Update,
+                (run_spawn_director, announce_cleared_waves)
+                    .chain()
+                    .run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
+#[derive(Deserialize, TypeUuid, TypePath, Debug)]
+#[uuid = "9d3f6a2e-41c7-4b0a-8e15-72c4d0b9a6f3"]
+pub struct WaveSchedule {
+    /// Spawning pauses while this many enemies are alive, counting ones placed by the level.
+    pub max_alive: usize,
+    /// Seconds between clearing a wave and the next one starting, and before the first.
+    pub rest_time: f32,
+    pub waves: Vec<WaveDefinition>,
+    /// Waves made up once the authored ones run out. Without it the director stops.
+    #[serde(default)]
+    pub procedural: Option<ProceduralWaves>,
+    #[serde(default)]
+    pub difficulty: DifficultyCurve,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WaveDefinition {
+    pub groups: Vec<WaveGroup>,
+    /// Seconds between spawns within the wave.
+    pub spawn_interval: f32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WaveGroup {
+    /// Name in `default.enemies.json`.
+    pub archetype: String,
+    pub count: u32,
+}
+
+/// Waves of randomly picked archetypes, growing with the difficulty.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ProceduralWaves {
+    pub base_count: u32,
+    /// Extra enemies per point of difficulty above 1.
+    pub count_per_difficulty: f32,
+    pub spawn_interval: f32,
+}
+
+/// How much harder the game gets, as a multiplier on enemy speed that starts at 1.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DifficultyCurve {
+    /// Added for every wave cleared.
+    pub per_wave: f32,
+    /// Added for every 1000 pixels the player has moved right of where they started.
+    pub per_distance: f32,
+    pub max: f32,
+}
+
+impl Default for DifficultyCurve {
+    fn default() -> Self {
+        DifficultyCurve {
+            per_wave: 0.0,
+            per_distance: 0.0,
+            max: 1.0,
+        }
+    }
+}
+
+impl DifficultyCurve {
+    fn at(&self, waves_cleared: usize, distance: f32) -> f32 {
+        let difficulty =
+            1.0 + self.per_wave * waves_cleared as f32 + self.per_distance * distance / 1000.0;
+        difficulty.clamp(1.0, self.max.max(1.0))
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct WaveAssets {
+    #[asset(path = "config/default.waves.json")]
+    pub schedule: Handle<WaveSchedule>,
+}
+
+/// Sent when every enemy of a wave has been defeated. Wave enemies that leave the camera behind
+/// are sent again rather than counted.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct WaveCleared {
+    /// Counting from 1.
+    pub wave: usize,
+}
+
+/// Marks enemies spawned by the director, so it can tell when a wave is cleared.
+#[derive(Component)]
+pub struct WaveEnemy;
+
+#[derive(Debug)]
+enum WavePhase {
+    Resting(Timer),
+    /// Archetypes still to spawn, in order.
+    Spawning {
+        queue: VecDeque<String>,
+        timer: Timer,
+    },
+    /// Everything is out; waiting for the wave to be defeated.
+    Fighting,
+}
+
+#[derive(Resource, Debug)]
+pub struct SpawnDirector {
+    /// Waves started so far.
+    wave: usize,
+    cleared: usize,
+    phase: WavePhase,
+    /// Seconds between spawns in the current wave.
+    spawn_interval: f32,
+    /// Enemies of the current wave that are still around, with their archetype.
+    alive: HashMap<Entity, String>,
+    /// Where the player was first seen, for distance based difficulty.
+    start_x: Option<f32>,
+}
+
+impl Default for SpawnDirector {
+    fn default() -> Self {
+        SpawnDirector {
+            wave: 0,
+            cleared: 0,
+            // Replaced with the schedule's rest time once it has loaded
+            phase: WavePhase::Resting(Timer::from_seconds(0.0, TimerMode::Once)),
+            spawn_interval: 0.0,
+            alive: HashMap::default(),
+            start_x: None,
+        }
+    }
+}
+
+impl SpawnDirector {
+    /// The archetypes and spawn interval of the next wave, or `None` once the schedule runs out.
+    fn next_wave(
+        &self,
+        schedule: &WaveSchedule,
+        archetypes: &EnemyArchetypes,
+        difficulty: f32,
+    ) -> Option<(VecDeque<String>, f32)> {
+        if let Some(wave) = schedule.waves.get(self.wave) {
+            let queue = wave
+                .groups
+                .iter()
+                .flat_map(|group| (0..group.count).map(|_| group.archetype.clone()))
+                .collect();
+            return Some((queue, wave.spawn_interval));
+        }
+
+        let procedural = schedule.procedural.as_ref()?;
+        let count = procedural.base_count
+            + ((difficulty - 1.0) * procedural.count_per_difficulty).round() as u32;
+        let mut rng = thread_rng();
+        let queue = (0..count)
+            .filter_map(|_| archetypes.choose(&mut rng).map(str::to_string))
+            .collect();
+        Some((queue, procedural.spawn_interval))
+    }
+
+    /// Puts an enemy of the current wave back in line to be spawned again.
+    fn requeue(&mut self, archetype: String) {
+        match &mut self.phase {
+            WavePhase::Spawning { queue, .. } => queue.push_back(archetype),
+            WavePhase::Fighting => {
+                self.phase = WavePhase::Spawning {
+                    queue: VecDeque::from([archetype]),
+                    timer: Timer::from_seconds(self.spawn_interval, TimerMode::Repeating),
+                };
+            }
+            // Nothing of the wave is left by then
+            WavePhase::Resting(_) => {}
+        }
+    }
+}
+
+pub fn run_spawn_director(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    mut director: ResMut<SpawnDirector>,
+    wave_assets: Res<WaveAssets>,
+    schedules: Res<Assets<WaveSchedule>>,
+    enemy_assets: Res<EnemyAssets>,
+    archetype_assets: Res<Assets<EnemyArchetypes>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<Camera>>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<(), With<Enemy>>,
+    terrain: Res<TerrainColliders>,
+    mut navigation: ResMut<Navigation>,
+    mut died_events: EventReader<Died>,
+    mut removed_wave_enemies: RemovedComponents<WaveEnemy>,
+    mut wave_cleared_events: EventWriter<WaveCleared>,
+) {
+    // Read before any early return so nothing is missed while the assets load
+    let died: HashSet<Entity> = died_events.iter().map(|died| died.entity).collect();
+    for entity in removed_wave_enemies.iter() {
+        let Some(archetype) = director.alive.remove(&entity) else {
+            continue;
+        };
+        // Gone without being defeated, e.g. despawned offscreen
+        if !died.contains(&entity) {
+            director.requeue(archetype);
+        }
+    }
+
+    let (Some(schedule), Some(archetypes)) = (
+        schedules.get(&wave_assets.schedule),
+        archetype_assets.get(&enemy_assets.archetypes),
+    ) else {
+        return;
+    };
+    let Ok((camera_transform, projection)) = camera_query.get_single() else {
+        return;
+    };
+    let player_position = player_query
+        .get_single()
+        .ok()
+        .map(|transform| transform.translation.truncate());
+
+    if director.start_x.is_none() {
+        director.start_x = player_position.map(|position| position.x);
+    }
+    let distance = match (player_position, director.start_x) {
+        (Some(position), Some(start_x)) => (position.x - start_x).max(0.0),
+        _ => 0.0,
+    };
+    let difficulty = schedule.difficulty.at(director.cleared, distance);
+
+    let director = director.as_mut();
+    match &mut director.phase {
+        WavePhase::Resting(timer) => {
+            if director.wave == 0 && timer.duration().is_zero() {
+                *timer = Timer::from_seconds(schedule.rest_time, TimerMode::Once);
+            }
+            if !timer.tick(time.delta()).finished() {
+                return;
+            }
+            let Some((queue, spawn_interval)) =
+                director.next_wave(schedule, archetypes, difficulty)
+            else {
+                return;
+            };
+            director.wave += 1;
+            director.spawn_interval = spawn_interval;
+            info!("Wave {} incoming: {} enemies", director.wave, queue.len());
+            // A wave can come out empty, e.g. with no groups or every spawn weight at zero
+            director.phase = if queue.is_empty() {
+                WavePhase::Fighting
+            } else {
+                WavePhase::Spawning {
+                    queue,
+                    timer: Timer::from_seconds(spawn_interval, TimerMode::Repeating),
+                }
+            };
+        }
+        WavePhase::Spawning { queue, timer } => {
+            timer.tick(time.delta());
+            // Spawns are held back rather than skipped while the cap is reached
+            if !timer.finished() || enemy_query.iter().count() >= schedule.max_alive {
+                return;
+            }
+
+            let Some(name) = queue.pop_front() else {
+                director.phase = WavePhase::Fighting;
+                return;
+            };
+            if let Some(archetype) = archetypes.get(&name) {
+                let mut archetype = archetype.clone();
+                archetype.speed *= difficulty;
+                let view = visible_area(camera_transform, projection);
+                // Walkers start where their path graph says they can stand, flyers anywhere empty
+                let grid = archetype.navigate.then(|| {
+                    let body = KinematicBody::new(Vec2::splat(archetype.size));
+                    navigation.grid(&terrain, clearance(&body))
+                });
+                let open = |cell: IVec2| match &grid {
+                    Some(grid) => grid.is_walkable(cell),
+                    None => terrain.tile(cell).is_none(),
+                };
+                let Some(position) =
+                    random_spawn_position(view, player_position, archetype.size, &terrain, open)
+                else {
+                    // Nowhere for it to fit yet, so try again on the next spawn
+                    queue.push_front(name);
+                    return;
+                };
+                let enemy = spawn_enemy(&mut commands, &asset_server, &archetype, position);
+                commands.entity(enemy).insert(WaveEnemy);
+                director.alive.insert(enemy, name);
+            } else {
+                warn!(
+                    "Wave {} spawns an unknown enemy archetype {:?}",
+                    director.wave, name
+                );
+            }
+
+            if queue.is_empty() {
+                director.phase = WavePhase::Fighting;
+            }
+        }
+        WavePhase::Fighting => {
+            if !director.alive.is_empty() {
+                return;
+            }
+            director.cleared += 1;
+            wave_cleared_events.send(WaveCleared {
+                wave: director.wave,
+            });
+            director.phase =
+                WavePhase::Resting(Timer::from_seconds(schedule.rest_time, TimerMode::Once));
+        }
+    }
+}
+
+fn announce_cleared_waves(mut wave_cleared_events: EventReader<WaveCleared>) {
+    for cleared in wave_cleared_events.iter() {
+        info!("Wave {} cleared", cleared.wave);
+    }
+}